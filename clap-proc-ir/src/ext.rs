@@ -1,8 +1,16 @@
 pub mod audio_ports;
 pub mod gui;
+pub mod latency;
+pub mod note_ports;
 pub mod params;
+pub mod posix_fd_support;
+pub mod registry;
+pub mod render;
 pub mod state;
 pub mod state_context;
+pub mod tail;
+pub mod timer_support;
+pub mod voice_info;
 
 use proc_macro2::TokenStream as TokenStream2;
 use quote::{ToTokens, format_ident, quote};
@@ -16,9 +24,16 @@ use syn::{Generics, Visibility};
 
 use audio_ports::PluginAudioPorts;
 use gui::PluginGUI;
+use latency::PluginLatency;
+use note_ports::PluginNotePorts;
 use params::PluginParams;
+use posix_fd_support::PluginPosixFdSupport;
+use render::PluginRender;
 use state::PluginState;
 use state_context::PluginStateContext;
+use tail::PluginTail;
+use timer_support::PluginTimerSupport;
+use voice_info::PluginVoiceInfo;
 
 pub fn parse(attrs: TokenStream2, input: TokenStream2) -> TokenStream2 {
     match syn::parse2(attrs) {
@@ -29,20 +44,58 @@ pub fn parse(attrs: TokenStream2, input: TokenStream2) -> TokenStream2 {
         Ok(ExtensionAttrs { extension }) if extension == "PluginGUI" => {
             ExtensionTokenizer::<PluginGUI>::throw_tokenize(input)
         }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginLatency" => {
+            ExtensionTokenizer::<PluginLatency>::throw_tokenize(input)
+        }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginNotePorts" => {
+            ExtensionTokenizer::<PluginNotePorts>::throw_tokenize(input)
+        }
         Ok(ExtensionAttrs { extension }) if extension == "PluginParams" => {
             ExtensionTokenizer::<PluginParams>::throw_tokenize(input)
         }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginPosixFdSupport" => {
+            ExtensionTokenizer::<PluginPosixFdSupport>::throw_tokenize(input)
+        }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginRender" => {
+            ExtensionTokenizer::<PluginRender>::throw_tokenize(input)
+        }
         Ok(ExtensionAttrs { extension }) if extension == "PluginState" => {
             ExtensionTokenizer::<PluginState>::throw_tokenize(input)
         }
         Ok(ExtensionAttrs { extension }) if extension == "PluginStateContext" => {
             ExtensionTokenizer::<PluginStateContext>::throw_tokenize(input)
         }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginTail" => {
+            ExtensionTokenizer::<PluginTail>::throw_tokenize(input)
+        }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginTimerSupport" => {
+            ExtensionTokenizer::<PluginTimerSupport>::throw_tokenize(input)
+        }
+        Ok(ExtensionAttrs { extension }) if extension == "PluginVoiceInfo" => {
+            ExtensionTokenizer::<PluginVoiceInfo>::throw_tokenize(input)
+        }
         Ok(ExtensionAttrs { extension }) => {
             syn::Error::new(extension.span(), "Unrecognized class").to_compile_error()
         }
     }
 }
+/// The vtable types of every extension `#[derive(ExtensionRegistry)]` probes for
+pub fn known_vtable_types() -> Vec<Type> {
+    vec![
+        PluginAudioPorts::vtable_type(),
+        PluginGUI::vtable_type(),
+        PluginLatency::vtable_type(),
+        PluginNotePorts::vtable_type(),
+        PluginParams::vtable_type(),
+        PluginPosixFdSupport::vtable_type(),
+        PluginRender::vtable_type(),
+        PluginState::vtable_type(),
+        PluginStateContext::vtable_type(),
+        PluginTail::vtable_type(),
+        PluginTimerSupport::vtable_type(),
+        PluginVoiceInfo::vtable_type(),
+    ]
+}
 pub struct ExtensionAttrs {
     pub extension: Ident,
 }
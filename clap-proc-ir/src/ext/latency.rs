@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginLatency;
+impl Extension for PluginLatency {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::latency::clap_plugin_latency }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::latency::extension_pointer()
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginNotePorts;
+impl Extension for PluginNotePorts {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::note_ports::clap_plugin_note_ports }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::note_ports::extension_pointer()
+        }
+    }
+}
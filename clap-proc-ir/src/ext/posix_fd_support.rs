@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginPosixFdSupport;
+impl Extension for PluginPosixFdSupport {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::posix_fd_support::clap_plugin_posix_fd_support }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::posix_fd_support::extension_pointer()
+        }
+    }
+}
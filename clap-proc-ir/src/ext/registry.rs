@@ -0,0 +1,91 @@
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{ToTokens, quote};
+
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{DeriveInput, GenericParam, Generics, Ident, Lifetime, Token, Type, parse_quote};
+
+use crate::ext::known_vtable_types;
+
+pub fn parse(input: TokenStream2) -> TokenStream2 {
+    match syn::parse2(input) {
+        Ok(registry @ ExtensionRegistry { .. }) => quote! { #registry },
+        Err(e) => e.to_compile_error(),
+    }
+}
+pub struct ExtensionRegistry {
+    pub ident: Ident,
+    pub generics: Generics,
+    pub vtable_types: Vec<Type>,
+}
+impl Parse for ExtensionRegistry {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let DeriveInput {
+            attrs,
+            ident,
+            generics,
+            ..
+        } = input.parse()?;
+        let mut vtable_types = known_vtable_types();
+        // Bases of extensions defined outside the crate, `#[extensions(Base, ...)]`
+        for attr in attrs
+            .iter()
+            .filter(|attr| attr.path().is_ident("extensions"))
+        {
+            vtable_types
+                .extend(attr.parse_args_with(Punctuated::<Type, Token![,]>::parse_terminated)?);
+        }
+        Ok(ExtensionRegistry {
+            ident,
+            generics,
+            vtable_types,
+        })
+    }
+}
+impl ExtensionRegistry {
+    /// The first lifetime of the plugin is taken to be the host-lifetime,
+    /// if there is none, one is introduced for the impl alone.
+    fn impl_generics(&self) -> (Generics, Lifetime) {
+        let mut generics = self.generics.clone();
+        if let Some(param) = generics.lifetimes().next() {
+            let lifetime = param.lifetime.clone();
+            return (generics, lifetime);
+        }
+        let lifetime: Lifetime = parse_quote! { 'host };
+        generics
+            .params
+            .insert(0, GenericParam::Lifetime(parse_quote! { #lifetime }));
+        (generics, lifetime)
+    }
+}
+impl ToTokens for ExtensionRegistry {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let ExtensionRegistry {
+            ident,
+            generics,
+            vtable_types,
+        } = self;
+        let (impl_generics, host_lifetime) = self.impl_generics();
+        let (impl_generics, _, where_clause) = impl_generics.split_for_impl();
+        let (_, type_generics, _) = generics.split_for_impl();
+        tokens.extend(quote! {
+            impl #impl_generics ::clap_prototype::ext::ExtensionRegistry<#host_lifetime> for #ident #type_generics
+            #where_clause
+            {
+                fn lookup(
+                    &self,
+                    id: &::clap_prototype::plugin::PluginExtensionID,
+                ) -> ::core::option::Option<*const ::core::ffi::c_void> {
+                    #[allow(unused_imports)]
+                    use ::clap_prototype::ext::registry::{MissingExtension as _, ProvidedExtension as _};
+                    ::core::option::Option::None
+                    #(
+                        .or_else(|| {
+                            (&::clap_prototype::ext::registry::ExtensionProbe::<Self, #vtable_types>::new(self)).probe(id)
+                        })
+                    )*
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginRender;
+impl Extension for PluginRender {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::render::clap_plugin_render }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::render::extension_pointer()
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginTail;
+impl Extension for PluginTail {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::tail::clap_plugin_tail }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::tail::extension_pointer()
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginTimerSupport;
+impl Extension for PluginTimerSupport {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::timer_support::clap_plugin_timer_support }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::timer_support::extension_pointer()
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use syn::{ExprCall, Type, parse_quote};
+
+use crate::ext::Extension;
+
+pub struct PluginVoiceInfo;
+impl Extension for PluginVoiceInfo {
+    fn vtable_type() -> Type {
+        parse_quote! { ::clap_sys::ext::voice_info::clap_plugin_voice_info }
+    }
+    fn extension_pointer_constructor() -> ExprCall {
+        parse_quote! {
+            ::clap_prototype::ext::voice_info::extension_pointer()
+        }
+    }
+}
@@ -22,6 +22,14 @@ pub fn parse_extension(attrs: TokenStream2, input: TokenStream2) -> TokenStream2
     ext::parse(attrs, input)
 }
 
+pub fn parse_extension_registry(input: TokenStream2) -> TokenStream2 {
+    ext::registry::parse(input)
+}
+
 pub fn parse_plugin_parameter(input: TokenStream2) -> TokenStream2 {
     plugin::parameter::parse(input)
 }
+
+pub fn parse_plugin_descriptor(input: TokenStream2) -> TokenStream2 {
+    plugin::descriptor::parse(input)
+}
@@ -1,2 +1,3 @@
+pub mod descriptor;
 pub mod entrypoint;
 pub mod parameter;
@@ -0,0 +1,244 @@
+use std::ffi::CString;
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+
+use quote::{ToTokens, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{Ident, Lit, LitCStr, Token, bracketed};
+
+pub fn parse(input: TokenStream2) -> TokenStream2 {
+    match syn::parse2(input) {
+        Ok(descriptor @ PluginDescriptor { .. }) => quote! { #descriptor },
+        Err(e) => e.to_compile_error(),
+    }
+}
+
+/// A string of the descriptor, given either as `"..."` or `c"..."`
+pub struct DescriptorString {
+    pub value: CString,
+    pub span: Span,
+}
+impl Parse for DescriptorString {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let lit: Lit = input.parse()?;
+        let span = lit.span();
+        let value = match lit {
+            Lit::Str(s) => CString::new(s.value())
+                .map_err(|_| syn::Error::new(span, "Strings of a descriptor cannot contain nul"))?,
+            Lit::CStr(s) => s.value(),
+            _ => return Err(syn::Error::new(span, "Expected a string literal")),
+        };
+        if value.to_str().is_err() {
+            return Err(syn::Error::new(
+                span,
+                "Strings of a descriptor must be valid UTF-8",
+            ));
+        }
+        Ok(DescriptorString { value, span })
+    }
+}
+impl DescriptorString {
+    fn is_empty(&self) -> bool {
+        self.value.is_empty()
+    }
+    fn as_str(&self) -> &str {
+        self.value.to_str().expect("checked on parse")
+    }
+}
+impl ToTokens for DescriptorString {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let lit = LitCStr::new(&self.value, self.span);
+        tokens.extend(quote! { #lit.as_ptr() });
+    }
+}
+
+pub enum Feature {
+    /// A variant of `PluginFeatureKind`
+    Kind(Ident),
+    /// A namespaced, non-standard feature
+    Custom(DescriptorString),
+}
+impl Parse for Feature {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Ident) {
+            return Ok(Feature::Kind(input.parse()?));
+        }
+        let custom: DescriptorString = input.parse()?;
+        if !custom.as_str().contains(':') {
+            return Err(syn::Error::new(
+                custom.span,
+                "Custom features must be namespaced, like \"vendor:feature\", use a PluginFeatureKind for the standard ones",
+            ));
+        }
+        Ok(Feature::Custom(custom))
+    }
+}
+impl Feature {
+    fn key(&self) -> String {
+        match self {
+            Feature::Kind(kind) => kind.to_string(),
+            Feature::Custom(custom) => custom.as_str().to_owned(),
+        }
+    }
+    fn span(&self) -> Span {
+        match self {
+            Feature::Kind(kind) => kind.span(),
+            Feature::Custom(custom) => custom.span,
+        }
+    }
+}
+impl ToTokens for Feature {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(match self {
+            Feature::Kind(kind) => quote! {
+                ::clap_prototype::plugin::features::PluginFeatureKind::#kind.as_c_str().as_ptr()
+            },
+            Feature::Custom(custom) => quote! { #custom },
+        });
+    }
+}
+
+pub enum DescriptorField {
+    String(Ident, DescriptorString),
+    Features(Ident, Punctuated<Feature, Token![,]>),
+}
+impl Parse for DescriptorField {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let key: Ident = input.parse()?;
+        let _: Token![:] = input.parse()?;
+        if key == "features" {
+            let content;
+            bracketed!(content in input);
+            return Ok(DescriptorField::Features(
+                key,
+                Punctuated::parse_terminated(&content)?,
+            ));
+        }
+        if !STRING_FIELDS.iter().any(|(field, _)| key == field) {
+            return Err(syn::Error::new_spanned(
+                key,
+                "Unknown field, expected one of \"id\", \"name\", \"vendor\", \"url\", \"manual_url\", \"support_url\", \"version\", \"description\" or \"features\"",
+            ));
+        }
+        Ok(DescriptorField::String(key, input.parse()?))
+    }
+}
+impl DescriptorField {
+    fn key(&self) -> &Ident {
+        match self {
+            DescriptorField::String(key, _) | DescriptorField::Features(key, _) => key,
+        }
+    }
+}
+
+/// The string fields of `clap_plugin_descriptor`, and whether they must not be empty
+const STRING_FIELDS: [(&str, bool); 8] = [
+    ("id", true),
+    ("name", true),
+    ("vendor", false),
+    ("url", false),
+    ("manual_url", false),
+    ("support_url", false),
+    ("version", true),
+    ("description", false),
+];
+
+pub struct PluginDescriptor {
+    pub strings: Vec<(Ident, Option<DescriptorString>)>,
+    pub features: Vec<Feature>,
+}
+impl Parse for PluginDescriptor {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let fields: Punctuated<DescriptorField, Token![,]> = Punctuated::parse_terminated(input)?;
+        let mut strings: Vec<(Ident, Option<DescriptorString>)> = STRING_FIELDS
+            .iter()
+            .map(|(field, _)| (Ident::new(field, Span::call_site()), None))
+            .collect();
+        let mut features: Option<Vec<Feature>> = None;
+        for field in fields {
+            let key = field.key().clone();
+            match field {
+                DescriptorField::String(key, value) => {
+                    let (_, slot) = strings
+                        .iter_mut()
+                        .find(|(field, _)| *field == key)
+                        .expect("string field");
+                    if slot.is_some() {
+                        return Err(syn::Error::new_spanned(key, "Duplicate field"));
+                    }
+                    *slot = Some(value);
+                }
+                DescriptorField::Features(_, list) => {
+                    if features.is_some() {
+                        return Err(syn::Error::new_spanned(key, "Duplicate field"));
+                    }
+                    let mut seen = Vec::new();
+                    for feature in &list {
+                        let feature_key = feature.key();
+                        if seen.contains(&feature_key) {
+                            return Err(syn::Error::new(feature.span(), "Duplicate feature"));
+                        }
+                        seen.push(feature_key);
+                    }
+                    features = Some(list.into_iter().collect());
+                }
+            }
+        }
+        for ((key, value), (_, required)) in strings.iter().zip(STRING_FIELDS) {
+            match value {
+                None if required => {
+                    return Err(syn::Error::new(
+                        input.span(),
+                        format!("Missing field \"{key}\""),
+                    ));
+                }
+                Some(value) if required && value.is_empty() => {
+                    return Err(syn::Error::new(
+                        value.span,
+                        format!("\"{key}\" cannot be empty"),
+                    ));
+                }
+                Some(value) if key == "id" && value.as_str().contains(char::is_whitespace) => {
+                    return Err(syn::Error::new(
+                        value.span,
+                        "The id cannot contain whitespace, use a reverse-domain name like \"com.vendor.plugin\"",
+                    ));
+                }
+                _ => {}
+            }
+        }
+        Ok(PluginDescriptor {
+            strings,
+            features: features.unwrap_or_default(),
+        })
+    }
+}
+impl ToTokens for PluginDescriptor {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let PluginDescriptor { strings, features } = self;
+        let string_fields = strings.iter().map(|(key, value)| match value {
+            Some(value) => quote! { #key: #value },
+            None => quote! { #key: c"".as_ptr() },
+        });
+        let feature_count = features.len() + 1;
+        tokens.extend(quote! {
+            {
+                static __PLUGIN_FEATURES: ::clap_prototype::plugin::features::StaticFeatureSet<#feature_count> =
+                    unsafe {
+                        ::clap_prototype::plugin::features::StaticFeatureSet::new([
+                            #(#features,)*
+                            ::core::ptr::null(),
+                        ])
+                    };
+                static __PLUGIN_DESCRIPTOR: ::clap_sys::plugin::clap_plugin_descriptor =
+                    ::clap_sys::plugin::clap_plugin_descriptor {
+                        clap_version: ::clap_sys::version::CLAP_VERSION,
+                        #(#string_fields,)*
+                        features: __PLUGIN_FEATURES.as_ptr(),
+                    };
+                &__PLUGIN_DESCRIPTOR
+            }
+        });
+    }
+}
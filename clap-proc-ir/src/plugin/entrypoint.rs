@@ -1,11 +1,18 @@
-use core::cmp::Ordering;
+use core::ffi::CStr;
 
-use proc_macro2::TokenStream as TokenStream2;
+use clap_sys::factory::draft::plugin_invalidation::CLAP_PLUGIN_INVALIDATION_FACTORY_ID;
+use clap_sys::factory::draft::plugin_state_converter::CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID;
+use clap_sys::factory::plugin_factory::CLAP_PLUGIN_FACTORY_ID;
+use clap_sys::factory::preset_discovery::{
+    CLAP_PRESET_DISCOVERY_FACTORY_ID, CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT,
+};
+use proc_macro2::{Span, TokenStream as TokenStream2};
 
-use quote::{ToTokens, quote};
+use quote::{ToTokens, quote, quote_spanned};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
-use syn::{Ident, Token};
+use syn::spanned::Spanned;
+use syn::{Ident, LitStr, Path, Token, braced};
 
 pub fn parse(input: TokenStream2) -> TokenStream2 {
     match syn::parse2(input) {
@@ -18,14 +25,16 @@ pub fn parse(input: TokenStream2) -> TokenStream2 {
 pub enum EntryFnIdent {
     Init,
     Deinit,
-    GetFactory,
+    /// The plugin factory alone, as a static
+    Factory,
+    Factories,
 }
 impl Parse for EntryFnIdent {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         if !input.peek(Ident) {
             return Err(syn::Error::new(
                 input.span(),
-                "Not an Ident, expected one of \"init\", \"deinit\", or \"get_factory\"",
+                "Not an Ident, expected one of \"init\", \"deinit\", \"factory\", or \"factories\"",
             ));
         };
 
@@ -35,117 +44,286 @@ impl Parse for EntryFnIdent {
             Ok(EntryFnIdent::Init)
         } else if value == "deinit" {
             Ok(EntryFnIdent::Deinit)
-        } else if value == "get_factory" {
-            Ok(EntryFnIdent::GetFactory)
+        } else if value == "factory" {
+            Ok(EntryFnIdent::Factory)
+        } else if value == "factories" {
+            Ok(EntryFnIdent::Factories)
         } else {
             Err(syn::Error::new_spanned(
                 value,
-                "Not an Ident, expected one of \"init\", \"deinit\", or \"get_factory\"",
+                "Not an Ident, expected one of \"init\", \"deinit\", \"factory\", or \"factories\"",
             ))
         }
     }
 }
+/// The factories the entrypoint can serve, by their current and compatibility ids,
+/// the ones `FactoryBase::matches` accepts, and the vtable type each is checked against
+const FACTORY_KINDS: [(&CStr, &[&CStr], &str); 4] = [
+    (
+        CLAP_PLUGIN_FACTORY_ID,
+        &[],
+        "::clap_sys::factory::plugin_factory::clap_plugin_factory",
+    ),
+    (
+        CLAP_PRESET_DISCOVERY_FACTORY_ID,
+        &[CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT],
+        "::clap_sys::factory::preset_discovery::clap_preset_discovery_factory",
+    ),
+    (
+        CLAP_PLUGIN_INVALIDATION_FACTORY_ID,
+        &[],
+        "::clap_sys::factory::draft::plugin_invalidation::clap_plugin_invalidation_factory",
+    ),
+    (
+        CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID,
+        &[],
+        "::clap_sys::factory::draft::plugin_state_converter::clap_plugin_state_converter_factory",
+    ),
+];
+/// Where a factory comes from, either a static, or a `fn() -> Option<&'static F>`
+pub enum FactorySource {
+    Static(Path),
+    Handler(Token![fn], Path),
+}
+impl Parse for FactorySource {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(Token![fn]) {
+            Ok(FactorySource::Handler(input.parse()?, input.parse()?))
+        } else {
+            Ok(FactorySource::Static(input.parse()?))
+        }
+    }
+}
+impl ToTokens for FactorySource {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        tokens.extend(match self {
+            FactorySource::Static(path) => quote! { ::core::option::Option::Some(&#path) },
+            FactorySource::Handler(_, path) => quote! { #path() },
+        });
+    }
+}
+pub struct FactoryMapping {
+    pub id: LitStr,
+    pub base: Path,
+    pub _arrow_token: Token![=>],
+    pub source: FactorySource,
+}
+impl Parse for FactoryMapping {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let id: LitStr = input.parse()?;
+        let value = id.value();
+        let Some((_, _, base)) = FACTORY_KINDS.iter().find(|(kind_id, compat_ids, _)| {
+            ::core::iter::once(kind_id)
+                .chain(compat_ids.iter())
+                .any(|kind_id| kind_id.to_bytes() == value.as_bytes())
+        }) else {
+            let expected: Vec<String> = FACTORY_KINDS
+                .iter()
+                .map(|(kind_id, _, _)| format!("{kind_id:?}"))
+                .collect();
+            return Err(syn::Error::new_spanned(
+                id,
+                format!(
+                    "Unknown factory id, expected one of {}",
+                    expected.join(", ")
+                ),
+            ));
+        };
+        Ok(FactoryMapping {
+            id,
+            base: syn::parse_str(base)?,
+            _arrow_token: input.parse()?,
+            source: input.parse()?,
+        })
+    }
+}
+impl ToTokens for FactoryMapping {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        let FactoryMapping { base, source, .. } = self;
+        // Points a factory of the wrong kind at the factory, not at the whole macro
+        let hand_out = quote_spanned! {source.span()=>
+            ::clap_prototype::factory::hand_out::<_, #base>(#source)
+        };
+        tokens.extend(quote! {
+            if <#base as ::clap_prototype::factory::FactoryBase>::matches(factory_id) {
+                return #hand_out;
+            }
+        });
+    }
+}
+pub struct FactoryTable(pub Punctuated<FactoryMapping, Token![,]>);
+impl Parse for FactoryTable {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let content;
+        braced!(content in input);
+        Ok(FactoryTable(Punctuated::parse_terminated(&content)?))
+    }
+}
+pub enum EntryValue {
+    Callee(Ident),
+    Factory(Path),
+    Factories(FactoryTable),
+}
 pub struct EntryFn {
+    pub span: Span,
     pub ident: EntryFnIdent,
     pub _colon_token: Token![:],
-    pub callee_ident: Ident,
+    pub value: EntryValue,
 }
 impl Parse for EntryFn {
     fn parse(input: ParseStream) -> syn::Result<Self> {
+        let span = input.span();
         let ident = input.parse()?;
         let _colon_token = input.parse()?;
-        let callee_ident = input.parse()?;
+        let value = match ident {
+            EntryFnIdent::Factory => EntryValue::Factory(input.parse()?),
+            EntryFnIdent::Factories => EntryValue::Factories(input.parse()?),
+            _ => EntryValue::Callee(input.parse()?),
+        };
         Ok(EntryFn {
+            span,
             ident,
             _colon_token,
-            callee_ident,
+            value,
         })
     }
 }
-impl ToTokens for EntryFn {
-    fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let EntryFn {
-            ident,
-            _colon_token,
-            callee_ident,
-        } = self;
-        let ffi_route = match ident {
-            EntryFnIdent::Init => {
-                quote! {
+/// `init` and `deinit` default to doing nothing, at least one factory is required.
+pub struct PluginEntrypoint {
+    pub init: Option<Ident>,
+    pub deinit: Option<Ident>,
+    pub factories: Vec<FactoryMapping>,
+}
 
-                unsafe extern "C" fn __plugin_entry_init(plugin_path: *const ::core::ffi::c_char) -> bool {
-                    let plugin_path = <&::clap_prototype::plugin::PluginPath as ::core::convert::TryFrom<*const ::core::ffi::c_char>>::try_from(plugin_path);
-                    match plugin_path {
-                        Ok(path) => #callee_ident(path),
-                        Err(e) => {
-                            println!("plugin-entry-init: {e:?}");
-                            false
-                        }
-                    }
-                }
-                    }
+impl Parse for PluginEntrypoint {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let inner: Punctuated<EntryFn, Token![,]> = Punctuated::parse_terminated(input)?;
+        let mut init = None;
+        let mut deinit = None;
+        let mut factory = None;
+        let mut factories: Option<Vec<FactoryMapping>> = None;
+        for EntryFn {
+            span, ident, value, ..
+        } in inner
+        {
+            let (name, taken) = match ident {
+                EntryFnIdent::Init => ("init", init.is_some()),
+                EntryFnIdent::Deinit => ("deinit", deinit.is_some()),
+                EntryFnIdent::Factory => ("factory", factory.is_some()),
+                EntryFnIdent::Factories => ("factories", factories.is_some()),
+            };
+            if taken {
+                return Err(syn::Error::new(span, format!("Duplicate \"{name}\"")));
             }
-            EntryFnIdent::Deinit => {
-                quote! {
-                    unsafe extern "C" fn __plugin_entry_deinit() {
-                        #callee_ident()
-                    }
+            match value {
+                EntryValue::Callee(callee_ident) if matches!(ident, EntryFnIdent::Init) => {
+                    init = Some(callee_ident)
                 }
-            }
-            EntryFnIdent::GetFactory => {
-                quote! {
-                unsafe extern "C" fn __plugin_entry_get_factory(factory_id: *const ::core::ffi::c_char) -> *const ::core::ffi::c_void {
-                    const fn __factory_assert<'host, T: ::clap_prototype::factory::PluginFactoryPrototype<'host>>(_: &'static T) {}
-                    let factory_id = <&::clap_prototype::plugin::PluginName as ::core::convert::TryFrom<*const ::core::ffi::c_char>>::try_from(factory_id);
-                    match factory_id {
-                        Ok(id) => {
-                            let factory: Option<&'static _> = #callee_ident(id);
-                            if let Some(factory) = factory {
-                                let _: () = __factory_assert(factory);
-                                factory as *const _ as *const _
-                            } else {
-                                ::core::ptr::null()
-                            }
-                        },
-                        Err(e) => {
-                            println!("plugin-get-factory: {e:?}");
-                            ::core::ptr::null()
-                        }
-                    }
+                EntryValue::Callee(callee_ident) => deinit = Some(callee_ident),
+                EntryValue::Factory(path) => {
+                    factory = Some(FactoryMapping {
+                        id: LitStr::new(&FACTORY_KINDS[0].0.to_string_lossy(), span),
+                        base: syn::parse_str(FACTORY_KINDS[0].2)?,
+                        _arrow_token: Default::default(),
+                        source: FactorySource::Static(path),
+                    })
                 }
+                EntryValue::Factories(FactoryTable(table)) => {
+                    factories = Some(table.into_iter().collect())
                 }
             }
-        };
-        tokens.extend(quote! { #ffi_route });
-    }
-}
-pub struct PluginEntrypoint(pub Punctuated<EntryFn, Token![,]>);
-
-impl Parse for PluginEntrypoint {
-    fn parse(input: ParseStream) -> syn::Result<Self> {
-        let inner = Punctuated::parse_terminated(input)?;
-        match PartialOrd::partial_cmp(&inner.len(), &3) {
-            Some(Ordering::Equal) => Ok(PluginEntrypoint(inner)),
-            Some(Ordering::Less) => Err(syn::Error::new_spanned(
-                inner,
-                "Missing an entrypoint function",
-            )),
-            Some(Ordering::Greater) => Err(syn::Error::new_spanned(
-                inner,
-                "Entrypoint has too many functions, there should \"init\", \"deinit\", and \"get_factory\"",
-            )),
-            None => panic!(),
         }
+        let factories: Vec<FactoryMapping> = factory
+            .into_iter()
+            .chain(factories.into_iter().flatten())
+            .collect();
+        if factories.is_empty() {
+            return Err(syn::Error::new(
+                Span::call_site(),
+                "Missing a factory, expected \"factory\" or \"factories\"",
+            ));
+        }
+        // A compatibility id names the same factory as its current one
+        let base = |mapping: &FactoryMapping| mapping.base.to_token_stream().to_string();
+        for (n, mapping) in factories.iter().enumerate() {
+            if factories[..n]
+                .iter()
+                .any(|previous| base(previous) == base(mapping))
+            {
+                return Err(syn::Error::new(
+                    mapping.id.span(),
+                    format!("Duplicate factory id \"{}\"", mapping.id.value()),
+                ));
+            }
+        }
+        Ok(PluginEntrypoint {
+            init,
+            deinit,
+            factories,
+        })
     }
 }
 
 impl ToTokens for PluginEntrypoint {
     fn to_tokens(&self, tokens: &mut TokenStream2) {
-        let PluginEntrypoint(list) = self;
-        for entry in list {
-            tokens.extend(quote! { #entry });
-        }
+        let PluginEntrypoint {
+            init,
+            deinit,
+            factories,
+        } = self;
+        let init = match init {
+            Some(callee_ident) => quote! { #callee_ident },
+            None => quote! { (|_: &::clap_prototype::plugin::PluginPath| true) },
+        };
+        let deinit = match deinit {
+            Some(callee_ident) => quote! { #callee_ident },
+            None => quote! { (|| {}) },
+        };
         tokens.extend(quote!{
+            static __PLUGIN_ENTRY_STATE: ::clap_prototype::entry::EntryState =
+                ::clap_prototype::entry::EntryState::new();
+
+            unsafe extern "C" fn __plugin_entry_init(plugin_path: *const ::core::ffi::c_char) -> bool {
+                ::clap_prototype::guard::call("clap_plugin_entry.init", || {
+                    let plugin_path = <&::clap_prototype::plugin::PluginPath as ::core::convert::TryFrom<*const ::core::ffi::c_char>>::try_from(plugin_path);
+                    match plugin_path {
+                        Ok(path) => __PLUGIN_ENTRY_STATE.init(|| #init(path)),
+                        Err(e) => {
+                            ::clap_prototype::log::log(
+                                ::clap_prototype::log::Severity::HostMisbehaving,
+                                format_args!("clap_plugin_entry.init: invalid plugin path: {e:?}"),
+                            );
+                            false
+                        }
+                    }
+                })
+            }
+            unsafe extern "C" fn __plugin_entry_deinit() {
+                ::clap_prototype::guard::call("clap_plugin_entry.deinit", || {
+                    __PLUGIN_ENTRY_STATE.deinit(|| #deinit())
+                })
+            }
+            unsafe extern "C" fn __plugin_entry_get_factory(factory_id: *const ::core::ffi::c_char) -> *const ::core::ffi::c_void {
+                ::clap_prototype::guard::call("clap_plugin_entry.get_factory", || {
+                    if factory_id.is_null() {
+                        ::clap_prototype::log::log(
+                            ::clap_prototype::log::Severity::HostMisbehaving,
+                            format_args!("clap_plugin_entry.get_factory: null factory id"),
+                        );
+                        return ::core::ptr::null();
+                    }
+                    if !__PLUGIN_ENTRY_STATE.is_initialized() {
+                        ::clap_prototype::log::log(
+                            ::clap_prototype::log::Severity::HostMisbehaving,
+                            format_args!("clap_plugin_entry.get_factory before init"),
+                        );
+                        return ::core::ptr::null();
+                    }
+                    let factory_id = unsafe { ::clap_prototype::factory::FactoryID::from_ptr(factory_id) };
+                    #(#factories)*
+                    ::core::ptr::null()
+                })
+            }
             #[allow(non_upper_case_globals)]
             #[unsafe(no_mangle)]
             pub static clap_entry: ::clap_sys::entry::clap_plugin_entry = ::clap_sys::entry::clap_plugin_entry {
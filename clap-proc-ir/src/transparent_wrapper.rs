@@ -92,10 +92,6 @@ impl<'entry> WrapperDefinition<'entry> {
         let WrapperDefinition { name, lifetime, .. } = self;
         let maybe_leading_reference: Option<Token![&]> =
             lifetime.is_none().then(|| parse_quote! { & });
-        println!(
-            "Leading reference is some {}",
-            maybe_leading_reference.is_some()
-        );
         parse_quote! { #maybe_leading_reference #name }
     }
     pub fn deref_impl(&self, target_type: Type, function_body: Expr) -> DerefImpl {
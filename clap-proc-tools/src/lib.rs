@@ -13,6 +13,11 @@ pub fn extends(attrs: TokenStream, input: TokenStream) -> TokenStream {
     ::ffi_wrap::extension(attrs.into(), input.into()).into()
 }
 
+#[proc_macro_derive(ExtensionRegistry, attributes(extensions))]
+pub fn extension_registry(input: TokenStream) -> TokenStream {
+    ::ffi_wrap::extension_registry(input.into()).into()
+}
+
 #[proc_macro]
 pub fn entrypoint(input: TokenStream) -> TokenStream {
     ::ffi_wrap::entrypoint(input.into()).into()
@@ -22,3 +27,8 @@ pub fn entrypoint(input: TokenStream) -> TokenStream {
 pub fn plugin_parameter(input: TokenStream) -> TokenStream {
     ::ffi_wrap::plugin_parameter(input.into()).into()
 }
+
+#[proc_macro]
+pub fn plugin_descriptor(input: TokenStream) -> TokenStream {
+    ::ffi_wrap::plugin_descriptor(input.into()).into()
+}
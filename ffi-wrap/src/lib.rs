@@ -16,7 +16,17 @@ pub fn extension(attrs: TokenStream2, input: TokenStream2) -> TokenStream2 {
     quote! { #item }
 }
 
+pub fn extension_registry(input: TokenStream2) -> TokenStream2 {
+    let item = clap_proc_ir::parse_extension_registry(input);
+    quote! { #item }
+}
+
 pub fn plugin_parameter(input: TokenStream2) -> TokenStream2 {
     let item = clap_proc_ir::parse_plugin_parameter(input);
     quote! {#item}
 }
+
+pub fn plugin_descriptor(input: TokenStream2) -> TokenStream2 {
+    let item = clap_proc_ir::parse_plugin_descriptor(input);
+    quote! {#item}
+}
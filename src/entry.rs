@@ -0,0 +1,102 @@
+//! Entry
+//!
+//! Hosts may call `clap_plugin_entry.init` and `deinit` several times, scanners
+//! in particular tend to load the library over and over. The [`EntryState`]
+//! generated by [`entrypoint!`](crate::entrypoint) counts them, so that the
+//! plugin's own `init` only runs on the first `init`, and its `deinit` on the
+//! last `deinit`. `get_factory` refuses to serve factories outside of them.
+
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::log::{self, Severity};
+
+/// The reference count of `clap_plugin_entry.init`
+pub struct EntryState {
+    count: Mutex<u32>,
+    initialized: AtomicBool,
+}
+impl Default for EntryState {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+impl EntryState {
+    pub const fn new() -> EntryState {
+        EntryState {
+            count: Mutex::new(0),
+            initialized: AtomicBool::new(false),
+        }
+    }
+    /// Runs `init` if this is the first `init`, which is not counted if it fails.
+    pub fn init<F>(&self, init: F) -> bool
+    where
+        F: FnOnce() -> bool,
+    {
+        let mut count = self
+            .count
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *count == 0 {
+            if !init() {
+                return false;
+            }
+            self.initialized.store(true, Ordering::Release);
+        }
+        *count += 1;
+        true
+    }
+    /// Runs `deinit` if this is the last `deinit`.
+    pub fn deinit<F>(&self, deinit: F)
+    where
+        F: FnOnce(),
+    {
+        let mut count = self
+            .count
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        match *count {
+            0 => log::log(
+                Severity::HostMisbehaving,
+                format_args!("clap_plugin_entry.deinit without a matching init"),
+            ),
+            1 => {
+                *count = 0;
+                self.initialized.store(false, Ordering::Release);
+                deinit();
+            }
+            _ => *count -= 1,
+        }
+    }
+    /// Whether the entry was initialized, and not yet deinitialized.
+    #[inline]
+    pub fn is_initialized(&self) -> bool {
+        self.initialized.load(Ordering::Acquire)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_init() {
+        let state = EntryState::new();
+        let mut inits = 0;
+        let mut deinits = 0;
+        assert!(!state.init(|| false));
+        assert!(!state.is_initialized());
+        assert!(state.init(|| {
+            inits += 1;
+            true
+        }));
+        assert!(state.init(|| unreachable!()));
+        assert!(state.is_initialized());
+        state.deinit(|| unreachable!());
+        assert!(state.is_initialized());
+        state.deinit(|| deinits += 1);
+        state.deinit(|| deinits += 1);
+        assert!(!state.is_initialized());
+        assert_eq!((inits, deinits), (1, 1));
+    }
+}
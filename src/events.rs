@@ -0,0 +1,490 @@
+//! Events
+//!
+//! Typed access to the event lists exchanged with the host, both during
+//! [`PluginPrototype::process`](crate::plugin::PluginPrototype::process) and
+//! [`PluginParamsPrototype::flush`](crate::ext::params::PluginParamsPrototype::flush).
+//!
+//! Events are sorted by time by the host, and every event of a list is valid
+//! for as long as the list itself.
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::mem::size_of;
+
+use clap_sys::events::{
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI_SYSEX, CLAP_EVENT_MIDI2,
+    CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
+    CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END,
+    CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT, clap_event_flags,
+    clap_event_header, clap_event_midi, clap_event_midi_sysex, clap_event_midi2, clap_event_note,
+    clap_event_note_expression, clap_event_param_gesture, clap_event_param_mod,
+    clap_event_param_value, clap_event_transport, clap_event_type, clap_input_events,
+    clap_output_events,
+};
+use clap_sys::id::clap_id;
+
+/// A MIDI system exclusive message
+#[derive(Debug, Clone, Copy)]
+pub struct MidiSysex<'ev> {
+    pub header: &'ev clap_event_header,
+    pub port_index: u16,
+    pub data: &'ev [u8],
+}
+
+/// An event of the core event space
+#[derive(Debug, Clone, Copy)]
+pub enum Event<'ev> {
+    NoteOn(&'ev clap_event_note),
+    NoteOff(&'ev clap_event_note),
+    NoteChoke(&'ev clap_event_note),
+    NoteEnd(&'ev clap_event_note),
+    NoteExpression(&'ev clap_event_note_expression),
+    ParamValue(&'ev clap_event_param_value),
+    ParamMod(&'ev clap_event_param_mod),
+    ParamGestureBegin(&'ev clap_event_param_gesture),
+    ParamGestureEnd(&'ev clap_event_param_gesture),
+    Transport(&'ev clap_event_transport),
+    Midi(&'ev clap_event_midi),
+    MidiSysex(MidiSysex<'ev>),
+    Midi2(&'ev clap_event_midi2),
+    /// A core event this crate does not know of, or one too small for its type
+    Unknown(&'ev clap_event_header),
+}
+impl<'ev> Event<'ev> {
+    /// Interprets the event behind `header`, according to its type.
+    ///
+    /// # Safety
+    ///
+    /// `header` must be the start of an event of `header.size` bytes, as
+    /// guaranteed by the host for the events it passes to the plugin.
+    pub unsafe fn from_header(header: &'ev clap_event_header) -> Event<'ev> {
+        /// Reinterprets the header as the event `T`, if the host says it is large enough
+        #[inline]
+        unsafe fn cast<T>(header: &clap_event_header) -> Option<&T> {
+            if (header.size as usize) < size_of::<T>() {
+                return None;
+            }
+            Some(unsafe { &*(header as *const clap_event_header as *const T) })
+        }
+        let event = unsafe {
+            match header.type_ {
+                CLAP_EVENT_NOTE_ON => cast(header).map(Event::NoteOn),
+                CLAP_EVENT_NOTE_OFF => cast(header).map(Event::NoteOff),
+                CLAP_EVENT_NOTE_CHOKE => cast(header).map(Event::NoteChoke),
+                CLAP_EVENT_NOTE_END => cast(header).map(Event::NoteEnd),
+                CLAP_EVENT_NOTE_EXPRESSION => cast(header).map(Event::NoteExpression),
+                CLAP_EVENT_PARAM_VALUE => cast(header).map(Event::ParamValue),
+                CLAP_EVENT_PARAM_MOD => cast(header).map(Event::ParamMod),
+                CLAP_EVENT_PARAM_GESTURE_BEGIN => cast(header).map(Event::ParamGestureBegin),
+                CLAP_EVENT_PARAM_GESTURE_END => cast(header).map(Event::ParamGestureEnd),
+                CLAP_EVENT_TRANSPORT => cast(header).map(Event::Transport),
+                CLAP_EVENT_MIDI => cast(header).map(Event::Midi),
+                CLAP_EVENT_MIDI_SYSEX => cast::<clap_event_midi_sysex>(header).map(|sysex| {
+                    let data = if sysex.buffer.is_null() {
+                        &[][..]
+                    } else {
+                        core::slice::from_raw_parts(sysex.buffer, sysex.size as usize)
+                    };
+                    Event::MidiSysex(MidiSysex {
+                        header,
+                        port_index: sysex.port_index,
+                        data,
+                    })
+                }),
+                CLAP_EVENT_MIDI2 => cast(header).map(Event::Midi2),
+                _ => None,
+            }
+        };
+        event.unwrap_or(Event::Unknown(header))
+    }
+    pub fn header(&self) -> &'ev clap_event_header {
+        match self {
+            Event::NoteOn(event)
+            | Event::NoteOff(event)
+            | Event::NoteChoke(event)
+            | Event::NoteEnd(event) => &event.header,
+            Event::NoteExpression(event) => &event.header,
+            Event::ParamValue(event) => &event.header,
+            Event::ParamMod(event) => &event.header,
+            Event::ParamGestureBegin(event) | Event::ParamGestureEnd(event) => &event.header,
+            Event::Transport(event) => &event.header,
+            Event::Midi(event) => &event.header,
+            Event::MidiSysex(event) => event.header,
+            Event::Midi2(event) => &event.header,
+            Event::Unknown(header) => header,
+        }
+    }
+    /// Sample offset of the event within the current block
+    #[inline]
+    pub fn time(&self) -> u32 {
+        self.header().time
+    }
+}
+
+/// The events the host sends to the plugin
+#[derive(Clone, Copy)]
+pub struct InputEvents<'ev> {
+    raw: &'ev clap_input_events,
+}
+impl<'ev> InputEvents<'ev> {
+    /// # Safety
+    ///
+    /// `raw` must be a list provided by the host, valid for `'ev`.
+    #[inline]
+    pub unsafe fn from_raw(raw: &'ev clap_input_events) -> InputEvents<'ev> {
+        InputEvents { raw }
+    }
+    /// A list without any events
+    #[inline]
+    pub fn empty() -> InputEvents<'static> {
+        static EMPTY: clap_input_events = clap_input_events {
+            ctx: ::core::ptr::null_mut(),
+            size: None,
+            get: None,
+        };
+        InputEvents { raw: &EMPTY }
+    }
+    #[inline]
+    pub fn as_raw(&self) -> &'ev clap_input_events {
+        self.raw
+    }
+    /// Number of events, of every event space
+    pub fn len(&self) -> u32 {
+        let Some(size) = self.raw.size else {
+            return 0;
+        };
+        unsafe { size(self.raw) }
+    }
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// The header of the event at `index`, of any event space
+    pub fn get(&self, index: u32) -> Option<&'ev clap_event_header> {
+        let get = self.raw.get?;
+        unsafe { get(self.raw, index).as_ref() }
+    }
+    /// The events of the core event space, in order.
+    pub fn iter(&self) -> impl Iterator<Item = Event<'ev>> + 'ev {
+        let this = *self;
+        self.iter_space(CLAP_CORE_EVENT_SPACE_ID)
+            .map(move |header| this.interpret(header))
+    }
+    /// The headers of the events of the event space `space_id`, in order.
+    pub fn iter_space(&self, space_id: u16) -> impl Iterator<Item = &'ev clap_event_header> + 'ev {
+        let this = *self;
+        (0..self.len())
+            .filter_map(move |index| this.get(index))
+            .filter(move |header| header.space_id == space_id)
+    }
+    #[inline]
+    fn interpret(&self, header: &'ev clap_event_header) -> Event<'ev> {
+        // SAFETY: The header comes from the host's list
+        unsafe { Event::from_header(header) }
+    }
+}
+
+/// Which notes an event applies to, `-1` being a wildcard for every field
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NoteIdentity {
+    pub note_id: i32,
+    pub port_index: i16,
+    pub channel: i16,
+    pub key: i16,
+}
+impl From<&clap_event_note> for NoteIdentity {
+    #[inline]
+    fn from(value: &clap_event_note) -> Self {
+        NoteIdentity {
+            note_id: value.note_id,
+            port_index: value.port_index,
+            channel: value.channel,
+            key: value.key,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamGesture {
+    Begin,
+    End,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum PushError {
+    /// The host did not accept the event, its queue is likely full
+    Rejected,
+    /// The list has no way of pushing events
+    Unsupported,
+}
+impl Display for PushError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            PushError::Rejected => f.write_str("host rejected the output event"),
+            PushError::Unsupported => f.write_str("output event list cannot be pushed to"),
+        }
+    }
+}
+
+/// The events the plugin sends to the host
+#[derive(Clone, Copy)]
+pub struct OutputEvents<'ev> {
+    raw: &'ev clap_output_events,
+    flags: clap_event_flags,
+}
+impl<'ev> OutputEvents<'ev> {
+    /// # Safety
+    ///
+    /// `raw` must be a list provided by the host, valid for `'ev`.
+    #[inline]
+    pub unsafe fn from_raw(raw: &'ev clap_output_events) -> OutputEvents<'ev> {
+        OutputEvents { raw, flags: 0 }
+    }
+    /// A list refusing every event
+    #[inline]
+    pub fn unsupported() -> OutputEvents<'static> {
+        static UNSUPPORTED: clap_output_events = clap_output_events {
+            ctx: ::core::ptr::null_mut(),
+            try_push: None,
+        };
+        OutputEvents {
+            raw: &UNSUPPORTED,
+            flags: 0,
+        }
+    }
+    #[inline]
+    pub fn as_raw(&self) -> &'ev clap_output_events {
+        self.raw
+    }
+    /// Flags set on the header of every event pushed through the returned list,
+    /// like `CLAP_EVENT_IS_LIVE` for changes made by the user through the GUI.
+    #[inline]
+    pub fn with_flags(self, flags: clap_event_flags) -> OutputEvents<'ev> {
+        OutputEvents { flags, ..self }
+    }
+    #[inline]
+    fn header<T>(&self, type_: clap_event_type, time: u32) -> clap_event_header {
+        clap_event_header {
+            size: size_of::<T>() as u32,
+            time,
+            space_id: CLAP_CORE_EVENT_SPACE_ID,
+            type_,
+            flags: self.flags,
+        }
+    }
+    /// Pushes an event as is, the host copies it.
+    ///
+    /// # Safety
+    ///
+    /// `header` must be the start of an event of `header.size` bytes.
+    pub unsafe fn try_push(&self, header: &clap_event_header) -> Result<(), PushError> {
+        let try_push = self.raw.try_push.ok_or(PushError::Unsupported)?;
+        if unsafe { try_push(self.raw, header) } {
+            Ok(())
+        } else {
+            Err(PushError::Rejected)
+        }
+    }
+    pub fn push_param_value(
+        &self,
+        time: u32,
+        param_id: clap_id,
+        value: f64,
+    ) -> Result<(), PushError> {
+        let event = clap_event_param_value {
+            header: self.header::<clap_event_param_value>(CLAP_EVENT_PARAM_VALUE, time),
+            param_id,
+            cookie: ::core::ptr::null_mut(),
+            note_id: -1,
+            port_index: -1,
+            channel: -1,
+            key: -1,
+            value,
+        };
+        unsafe { self.try_push(&event.header) }
+    }
+    /// Marks the start or the end of the user adjusting a parameter,
+    /// so the host can group the changes in between, for undo or automation recording.
+    pub fn push_gesture(
+        &self,
+        time: u32,
+        param_id: clap_id,
+        gesture: ParamGesture,
+    ) -> Result<(), PushError> {
+        let type_ = match gesture {
+            ParamGesture::Begin => CLAP_EVENT_PARAM_GESTURE_BEGIN,
+            ParamGesture::End => CLAP_EVENT_PARAM_GESTURE_END,
+        };
+        let event = clap_event_param_gesture {
+            header: self.header::<clap_event_param_gesture>(type_, time),
+            param_id,
+        };
+        unsafe { self.try_push(&event.header) }
+    }
+    /// Tells the host a voice has ended, so it can stop modulating the note.
+    pub fn push_note_end(&self, time: u32, note: NoteIdentity) -> Result<(), PushError> {
+        let NoteIdentity {
+            note_id,
+            port_index,
+            channel,
+            key,
+        } = note;
+        let event = clap_event_note {
+            header: self.header::<clap_event_note>(CLAP_EVENT_NOTE_END, time),
+            note_id,
+            port_index,
+            channel,
+            key,
+            velocity: 0.0,
+        };
+        unsafe { self.try_push(&event.header) }
+    }
+    pub fn push_midi(&self, time: u32, port_index: u16, data: [u8; 3]) -> Result<(), PushError> {
+        let event = clap_event_midi {
+            header: self.header::<clap_event_midi>(CLAP_EVENT_MIDI, time),
+            port_index,
+            data,
+        };
+        unsafe { self.try_push(&event.header) }
+    }
+}
+
+/// A `clap_input_events` over `list`, for the tests of anything reading events
+#[cfg(test)]
+pub(crate) fn test_input_events(list: &Vec<*const clap_event_header>) -> clap_input_events {
+    unsafe extern "C" fn size(list: *const clap_input_events) -> u32 {
+        let events = unsafe { &*((*list).ctx as *const Vec<*const clap_event_header>) };
+        events.len() as u32
+    }
+    unsafe extern "C" fn get(
+        list: *const clap_input_events,
+        index: u32,
+    ) -> *const clap_event_header {
+        let events = unsafe { &*((*list).ctx as *const Vec<*const clap_event_header>) };
+        events
+            .get(index as usize)
+            .copied()
+            .unwrap_or(::core::ptr::null())
+    }
+    clap_input_events {
+        ctx: list as *const _ as *mut ::core::ffi::c_void,
+        size: Some(size),
+        get: Some(get),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use core::ffi::c_void;
+
+    use clap_sys::events::CLAP_EVENT_IS_LIVE;
+
+    fn header<T>(type_: u16, space_id: u16, time: u32) -> clap_event_header {
+        clap_event_header {
+            size: size_of::<T>() as u32,
+            time,
+            space_id,
+            type_,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn iterates_core_events() {
+        let note = clap_event_note {
+            header: header::<clap_event_note>(CLAP_EVENT_NOTE_ON, CLAP_CORE_EVENT_SPACE_ID, 3),
+            note_id: -1,
+            port_index: 0,
+            channel: 0,
+            key: 60,
+            velocity: 1.0,
+        };
+        let foreign = header::<clap_event_header>(CLAP_EVENT_NOTE_ON, 7, 4);
+        let sysex_data = [0xF0_u8, 0x7E, 0xF7];
+        let sysex = clap_event_midi_sysex {
+            header: header::<clap_event_midi_sysex>(CLAP_EVENT_MIDI_SYSEX, 0, 5),
+            port_index: 1,
+            buffer: sysex_data.as_ptr(),
+            size: sysex_data.len() as u32,
+        };
+        let truncated = header::<clap_event_header>(CLAP_EVENT_PARAM_VALUE, 0, 6);
+        let list: Vec<*const clap_event_header> =
+            vec![&note.header, &foreign, &sysex.header, &truncated];
+        let raw = test_input_events(&list);
+        let events = unsafe { InputEvents::from_raw(&raw) };
+
+        assert_eq!(events.len(), 4);
+        let core: Vec<_> = events.iter().collect();
+        assert_eq!(core.len(), 3);
+        assert!(matches!(
+            core[0],
+            Event::NoteOn(clap_event_note { key: 60, .. })
+        ));
+        assert!(matches!(
+            core[1],
+            Event::MidiSysex(MidiSysex {
+                port_index: 1,
+                data: [0xF0, 0x7E, 0xF7],
+                ..
+            })
+        ));
+        assert!(matches!(core[2], Event::Unknown(_)));
+        assert_eq!(core.iter().map(Event::time).collect::<Vec<_>>(), [3, 5, 6]);
+        assert_eq!(events.iter_space(7).count(), 1);
+    }
+
+    unsafe extern "C" fn try_push(
+        list: *const clap_output_events,
+        event: *const clap_event_header,
+    ) -> bool {
+        let pushed = unsafe { &mut *((*list).ctx as *mut Vec<clap_event_header>) };
+        if pushed.len() == 2 {
+            return false;
+        }
+        pushed.push(unsafe { *event });
+        true
+    }
+
+    #[test]
+    fn pushes_headers() {
+        let mut pushed: Vec<clap_event_header> = Vec::new();
+        let raw = clap_output_events {
+            ctx: &mut pushed as *mut _ as *mut c_void,
+            try_push: Some(try_push),
+        };
+        let events = unsafe { OutputEvents::from_raw(&raw) }.with_flags(CLAP_EVENT_IS_LIVE);
+
+        assert_eq!(events.push_gesture(0, 3, ParamGesture::Begin), Ok(()));
+        assert_eq!(events.push_param_value(2, 3, 0.5), Ok(()));
+        assert_eq!(
+            events.push_midi(4, 0, [0x90, 60, 127]),
+            Err(PushError::Rejected)
+        );
+        assert_eq!(
+            OutputEvents::unsupported().push_gesture(0, 3, ParamGesture::End),
+            Err(PushError::Unsupported)
+        );
+
+        let expected = [
+            (
+                size_of::<clap_event_param_gesture>(),
+                CLAP_EVENT_PARAM_GESTURE_BEGIN,
+                0,
+            ),
+            (
+                size_of::<clap_event_param_value>(),
+                CLAP_EVENT_PARAM_VALUE,
+                2,
+            ),
+        ];
+        assert_eq!(pushed.len(), expected.len());
+        for (header, (size, type_, time)) in pushed.iter().zip(expected) {
+            assert_eq!(header.size as usize, size);
+            assert_eq!(header.type_, type_);
+            assert_eq!(header.time, time);
+            assert_eq!(header.space_id, CLAP_CORE_EVENT_SPACE_ID);
+            assert_eq!(header.flags, CLAP_EVENT_IS_LIVE);
+        }
+    }
+}
@@ -1,13 +1,149 @@
+use core::ffi::{CStr, c_void};
+
+use crate::plugin::PluginExtensionID;
 use crate::{AbstractPrototype, ProtoPtr};
 
 pub mod audio_ports;
 pub mod gui;
+pub mod latency;
+pub mod note_ports;
 pub mod params;
+pub mod posix_fd_support;
+pub mod registry;
+pub mod render;
 pub mod state;
 pub mod state_context;
+pub mod tail;
+pub mod timer_support;
+pub mod voice_info;
 
-pub use clap_proc_tools::extends;
+pub use clap_proc_tools::{ExtensionRegistry, extends};
 
 pub trait ExtensionPrototype<'host>: AbstractPrototype<'host> {
     type Parent: AbstractPrototype<'host>;
 }
+
+/// Implemented by the vtable type of every plugin-side extension,
+/// ties it to the identifiers the host may query it by.
+pub trait PluginExtensionBase {
+    const ID: &'static CStr;
+    /// Older draft or compatibility identifiers, which should resolve to the
+    /// same vtable as [`PluginExtensionBase::ID`].
+    const COMPAT_IDS: &'static [&'static CStr] = &[];
+    #[inline]
+    fn matches(id: &PluginExtensionID) -> bool {
+        id == Self::ID || Self::COMPAT_IDS.iter().any(|compat_id| id == *compat_id)
+    }
+}
+/// Answers the host's `clap_plugin::get_extension`.
+///
+/// Can be derived, in which case every extension the plugin has a
+/// [`HasExtension`](crate::plugin::HasExtension) implementation for is discoverable.
+/// Extensions this crate does not know of have their base listed in
+/// `#[extensions(...)]` next to the derive.
+pub trait ExtensionRegistry<'host> {
+    /// `thread-safe`
+    ///
+    /// Returns the extension vtable registered under `id`, if any.
+    /// The returned pointer must stay valid for as long as the plugin lives.
+    fn lookup(&self, id: &PluginExtensionID) -> Option<*const c_void>;
+}
+
+/// Declares a set of flags over a raw clap bitmask, with a constant per flag.
+///
+/// Bits which are not a declared flag can only be dropped, see `from_bits_truncate`.
+macro_rules! clap_flags {
+    (
+        $(#[$meta:meta])*
+        $name:ident($bits:ty) {
+            $($(#[$flag_meta:meta])* $flag:ident = $value:expr;)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+        pub struct $name($bits);
+        impl $name {
+            $($(#[$flag_meta])* pub const $flag: $name = $name($value);)*
+            const KNOWN_BITS: $bits = 0 $(| $value)*;
+
+            #[inline]
+            pub const fn empty() -> $name {
+                $name(0)
+            }
+            /// Drops the bits which are not a known flag.
+            #[inline]
+            pub const fn from_bits_truncate(bits: $bits) -> $name {
+                $name(bits & Self::KNOWN_BITS)
+            }
+            #[inline]
+            pub const fn bits(&self) -> $bits {
+                self.0
+            }
+            #[inline]
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+            /// Whether every flag of `other` is set.
+            #[inline]
+            pub const fn contains(&self, other: $name) -> bool {
+                self.0 & other.0 == other.0
+            }
+            /// Whether any flag of `other` is set.
+            #[inline]
+            pub const fn intersects(&self, other: $name) -> bool {
+                self.0 & other.0 != 0
+            }
+            #[inline]
+            pub const fn union(self, other: $name) -> $name {
+                $name(self.0 | other.0)
+            }
+        }
+        impl ::core::ops::BitOr for $name {
+            type Output = $name;
+            #[inline]
+            fn bitor(self, rhs: $name) -> $name {
+                self.union(rhs)
+            }
+        }
+        impl ::core::ops::BitOrAssign for $name {
+            #[inline]
+            fn bitor_assign(&mut self, rhs: $name) {
+                *self = self.union(rhs);
+            }
+        }
+        impl ::core::ops::BitAnd for $name {
+            type Output = $name;
+            #[inline]
+            fn bitand(self, rhs: $name) -> $name {
+                $name(self.0 & rhs.0)
+            }
+        }
+    };
+}
+pub(crate) use clap_flags;
+
+#[cfg(test)]
+mod tests {
+    clap_flags! {
+        TestFlags(u32) {
+            A = 1 << 0;
+            B = 1 << 1;
+            C = 1 << 3;
+        }
+    }
+
+    #[test]
+    fn flags() {
+        let flags = TestFlags::A | TestFlags::C;
+        assert!(flags.contains(TestFlags::A));
+        assert!(flags.contains(TestFlags::A | TestFlags::C));
+        assert!(!flags.contains(TestFlags::A | TestFlags::B));
+        assert!(flags.intersects(TestFlags::A | TestFlags::B));
+        assert!(!flags.intersects(TestFlags::B));
+        assert!(flags.contains(TestFlags::empty()));
+        assert_eq!(flags & TestFlags::B, TestFlags::empty());
+        assert_eq!(flags.bits(), 0b1001);
+        assert_eq!(TestFlags::from_bits_truncate(0b1_0110), TestFlags::B);
+        assert!(TestFlags::from_bits_truncate(1 << 7).is_empty());
+    }
+}
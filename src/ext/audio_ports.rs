@@ -1,23 +1,36 @@
-use crate::ext::{ExtensionPrototype, ProtoPtr};
+use core::ffi::CStr;
+
+use crate::ext::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
+use crate::guard;
 use crate::plugin::HasExtension;
+use crate::thread::{self, MainThread};
 
-use clap_sys::ext::audio_ports::{clap_audio_port_info, clap_plugin_audio_ports};
+use clap_sys::ext::audio_ports::{
+    CLAP_EXT_AUDIO_PORTS, clap_audio_port_info, clap_plugin_audio_ports,
+};
 use clap_sys::plugin::clap_plugin;
 
 pub trait PluginAudioPortsPrototype<'host>:
     ExtensionPrototype<'host, Base = clap_plugin_audio_ports>
 {
-    fn count(&self, is_input: bool) -> u32;
-    fn get(&self, index: u32, is_input: bool) -> Option<&clap_audio_port_info>;
+    fn count(&self, main_thread: MainThread, is_input: bool) -> u32;
+    fn get(
+        &self,
+        main_thread: MainThread,
+        index: u32,
+        is_input: bool,
+    ) -> Option<&clap_audio_port_info>;
 }
 
+impl PluginExtensionBase for clap_plugin_audio_ports {
+    const ID: &'static CStr = CLAP_EXT_AUDIO_PORTS;
+}
 fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
 where
     P: HasExtension<'host, clap_plugin_audio_ports, ExtensionType = E> + 'ext,
     E: PluginAudioPortsPrototype<'host>,
     'host: 'ext,
 {
-    println!("GET EXT AUDIO PORTS");
     let plugin = unsafe { ptr.as_ref() }?;
     let parent = unsafe { (plugin.plugin_data as *const P).as_ref() }?;
     Some(parent.get_extension())
@@ -28,11 +41,13 @@ where
     P: HasExtension<'host, clap_plugin_audio_ports, ExtensionType = E>,
     E: PluginAudioPortsPrototype<'host>,
 {
-    println!("EXTERN FN AUDIO PORTS COUNT");
-    let Some(ext) = get_ext::<P, E>(plugin_ptr) else {
-        return 0;
-    };
-    ext.count(is_input)
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_audio_ports.count", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_audio_ports.count");
+        let Some(ext) = get_ext::<P, E>(plugin_ptr) else {
+            return 0;
+        };
+        ext.count(main_thread, is_input)
+    })
 }
 
 #[inline(never)]
@@ -46,16 +61,18 @@ where
     P: HasExtension<'host, clap_plugin_audio_ports, ExtensionType = E>,
     E: PluginAudioPortsPrototype<'host>,
 {
-    println!("EXTERN FN AUDIO PORTS GET");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    if let Some(info_src) = ext.get(index, is_input) {
-        unsafe { core::ptr::copy_nonoverlapping(info_src, info_dst, 1) };
-        true
-    } else {
-        false
-    }
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_audio_ports.get", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_audio_ports.get");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        if let Some(info_src) = ext.get(main_thread, index, is_input) {
+            unsafe { core::ptr::copy_nonoverlapping(info_src, info_dst, 1) };
+            true
+        } else {
+            false
+        }
+    })
 }
 pub const fn vtable<'host, P, E>() -> &'static clap_plugin_audio_ports
 where
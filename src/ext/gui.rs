@@ -37,18 +37,22 @@
 //! 3. clap_plugin_gui->adjust_size(new_size) -> working_size
 //! 4. clap_plugin_gui->set_size(working_size)
 
-use core::ffi::c_char;
+use core::ffi::{CStr, c_char};
 
 use crate::ProtoPtr;
+use crate::guard;
+use crate::log::Severity;
+use crate::thread::{self, MainThread};
 
 use crate::plugin::HasExtension;
+use crate::plugin::instance::PluginInstance;
 use crate::plugin::{PluginGUIWindowAPIName, PluginGUIWindowTitle};
 
-use crate::ext::ExtensionPrototype;
+use crate::ext::{ExtensionPrototype, PluginExtensionBase};
 
 use clap_sys::plugin::clap_plugin;
 
-use clap_sys::ext::gui::clap_plugin_gui;
+use clap_sys::ext::gui::{CLAP_EXT_GUI, clap_plugin_gui};
 use clap_sys::ext::gui::{clap_gui_resize_hints, clap_window};
 
 pub enum PluginGUIWindowAPI {
@@ -107,12 +111,16 @@ pub trait PluginGUIPrototype<'host>: ExtensionPrototype<'host, Base = clap_plugi
     /// `main-thread`
     ///
     /// Returns true if the requested gui api is supported
-    fn is_api_supported(&self, window_details: PluginGUIWindowAPIDetails) -> bool;
+    fn is_api_supported(
+        &self,
+        main_thread: MainThread,
+        window_details: PluginGUIWindowAPIDetails,
+    ) -> bool;
     /// `main-thread`
     ///
     /// Returns true if the plugin has a preferred api.
     /// The host has no obligation to honor the plugin preference, this is just a hint.
-    fn get_preferred_api(&self) -> Option<PluginGUIWindowAPIDetails>;
+    fn get_preferred_api(&self, main_thread: MainThread) -> Option<PluginGUIWindowAPIDetails>;
     /// `main-thread`
     ///
     /// Create and allocate all resources necessary for the gui.
@@ -127,11 +135,11 @@ pub trait PluginGUIPrototype<'host>: ExtensionPrototype<'host, Base = clap_plugi
     /// After this call, the GUI may not be visible yet; don't forget to call show().
     ///
     /// Returns true if the GUI is successfully created.
-    fn create(&self, window_details: PluginGUIWindowAPIDetails) -> bool;
+    fn create(&self, main_thread: MainThread, window_details: PluginGUIWindowAPIDetails) -> bool;
     /// `main-thread`
     ///
     /// Free all resources associated with the gui.
-    fn destroy(&self);
+    fn destroy(&self, main_thread: MainThread);
     /// `main-thread`
     ///
     /// Set the absolute GUI scaling factor, and override any OS info.
@@ -144,22 +152,22 @@ pub trait PluginGUIPrototype<'host>: ExtensionPrototype<'host, Base = clap_plugi
     ///
     /// Returns true if the scaling could be applied
     /// Returns false if the call was ignored, or the scaling could not be applied.
-    fn set_scale(&self, scale: f64) -> bool;
+    fn set_scale(&self, main_thread: MainThread, scale: f64) -> bool;
     /// `main-thread`
     ///
     /// Get the current size of the plugin UI.
     /// clap_plugin_gui->create() must have been called prior to asking the size.
     ///
     /// Returns true if the plugin could get the size.
-    fn get_size(&self) -> Option<PluginGUIWindowSize>;
+    fn get_size(&self, main_thread: MainThread) -> Option<PluginGUIWindowSize>;
     /// `main-thread` & `!floating`
     ///
     /// Returns true if the window is resizeable (mouse drag).
-    fn can_resize(&self) -> bool;
+    fn can_resize(&self, main_thread: MainThread) -> bool;
     /// `main-thread` & `!floating`
     ///
     /// Returns true if the plugin can provide hints on how to resize the window.
-    fn get_resize_hints(&self) -> Option<clap_gui_resize_hints>;
+    fn get_resize_hints(&self, main_thread: MainThread) -> Option<clap_gui_resize_hints>;
     /// `main-thread` & `!floating`
     ///
     /// If the plugin gui is resizable, then the plugin will calculate the closest
@@ -167,41 +175,44 @@ pub trait PluginGUIPrototype<'host>: ExtensionPrototype<'host, Base = clap_plugi
     /// This method does not change the size.
     ///
     /// Returns true if the plugin could adjust the given size.
-    fn adjust_size(&self, given_size: &mut PluginGUIWindowSize) -> bool;
+    fn adjust_size(&self, main_thread: MainThread, given_size: &mut PluginGUIWindowSize) -> bool;
     /// `main-thread` & `!floating`
     ///
     /// Sets the window size.
     ///
     /// Returns true if the plugin could resize its window to the given size.
-    fn set_size(&self, target_size: PluginGUIWindowSize) -> bool;
+    fn set_size(&self, main_thread: MainThread, target_size: PluginGUIWindowSize) -> bool;
     /// `main-thread` & `!floating`
     ///
     /// Embeds the plugin window into the given window.
     ///
     /// Returns true on success.
-    fn set_parent(&self, window: &'host clap_window) -> bool;
+    fn set_parent(&self, main_thread: MainThread, window: &'host clap_window) -> bool;
     /// `main-thread` & `floating`
     /// Set the plugin floating window to stay above the given window.
     ///
     /// Returns true on success.
-    fn set_transient(&self, window: &'host clap_window) -> bool;
+    fn set_transient(&self, main_thread: MainThread, window: &'host clap_window) -> bool;
     /// `main-thread` & `floating`
     ///
     /// Suggests a window title. Only for floating windows.
-    fn suggest_title(&self, title: &PluginGUIWindowTitle);
+    fn suggest_title(&self, main_thread: MainThread, title: &PluginGUIWindowTitle);
     /// `main-thread`
     ///
     /// Show the window.
     ///
     /// Returns true on success.
-    fn show(&self) -> bool;
+    fn show(&self, main_thread: MainThread) -> bool;
     /// `main-thread`
     ///
     /// Hide the window, this method does not free the resources, it just hides
     /// the window content. Yet it may be a good idea to stop painting timers.
     ///
     /// Returns true on success.
-    fn hide(&self) -> bool;
+    fn hide(&self, main_thread: MainThread) -> bool;
+}
+impl PluginExtensionBase for clap_plugin_gui {
+    const ID: &'static CStr = CLAP_EXT_GUI;
 }
 fn get_ext<'host, 'ext, P, E>(plugin: *const clap_plugin) -> Option<&'ext E>
 where
@@ -222,18 +233,26 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_IS_API_SUPPORTED");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let name = unsafe { PluginGUIWindowAPIName::from_ptr(api) };
-    let Some(window_api) = PluginGUIWindowAPI::maybe_from(name) else {
-        println!("What API is {name:?} tf");
-        return false;
-    };
-    ext.is_api_supported(PluginGUIWindowAPIDetails {
-        window_api,
-        is_floating,
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.is_api_supported", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.is_api_supported");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let name = unsafe { PluginGUIWindowAPIName::from_ptr(api) };
+        let Some(window_api) = PluginGUIWindowAPI::maybe_from(name) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::Warning,
+                format_args!("unknown window api {name:?}"),
+            );
+            return false;
+        };
+        ext.is_api_supported(
+            main_thread,
+            PluginGUIWindowAPIDetails {
+                window_api,
+                is_floating,
+            },
+        )
     })
 }
 unsafe extern "C" fn get_preferred_api<'host, P, E>(
@@ -245,24 +264,25 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_GET_PREFERRED_API");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(PluginGUIWindowAPIDetails {
-        window_api,
-        is_floating: is_floating_window,
-    }) = ext.get_preferred_api()
-    else {
-        println!("no preferred api returned");
-        return false;
-    };
-    let name: &'static PluginGUIWindowAPIName = window_api.into();
-    unsafe {
-        *api = name.as_ptr();
-        *out_is_floating = is_floating_window;
-    };
-    true
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.get_preferred_api", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.get_preferred_api");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(PluginGUIWindowAPIDetails {
+            window_api,
+            is_floating: is_floating_window,
+        }) = ext.get_preferred_api(main_thread)
+        else {
+            return false;
+        };
+        let name: &'static PluginGUIWindowAPIName = window_api.into();
+        unsafe {
+            *api = name.as_ptr();
+            *out_is_floating = is_floating_window;
+        };
+        true
+    })
 }
 unsafe extern "C" fn create<'host, P, E>(
     plugin: *const clap_plugin,
@@ -273,18 +293,31 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_CREATE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let name = unsafe { PluginGUIWindowAPIName::from_ptr(api) };
-    let Some(window_api) = PluginGUIWindowAPI::maybe_from(name) else {
-        println!("What API is {name:?} tf");
-        return false;
-    };
-    ext.create(PluginGUIWindowAPIDetails {
-        window_api,
-        is_floating: in_is_floating,
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.create", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.create");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let name = unsafe { PluginGUIWindowAPIName::from_ptr(api) };
+        let Some(window_api) = PluginGUIWindowAPI::maybe_from(name) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::Warning,
+                format_args!("unknown window api {name:?}"),
+            );
+            return false;
+        };
+        let created = ext.create(
+            main_thread,
+            PluginGUIWindowAPIDetails {
+                window_api,
+                is_floating: in_is_floating,
+            },
+        );
+        if created && let Some(instance) = PluginInstance::<P>::from_plugin(plugin) {
+            // Make sure the GUI goes away with the plugin, should the host not destroy it
+            instance.on_teardown(CLAP_EXT_GUI, destroy::<'host, P, E>);
+        }
+        created
     })
 }
 unsafe extern "C" fn destroy<'host, P, E>(plugin: *const clap_plugin)
@@ -292,22 +325,29 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_DESTROY");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return;
-    };
-    ext.destroy();
+    guard::release_call::<P, _, _>(plugin, "clap_plugin_gui.destroy", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.destroy");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return;
+        };
+        if let Some(instance) = PluginInstance::<P>::from_plugin(plugin) {
+            instance.cancel_teardown(CLAP_EXT_GUI);
+        }
+        ext.destroy(main_thread);
+    })
 }
 unsafe extern "C" fn set_scale<'host, P, E>(plugin: *const clap_plugin, scale: f64) -> bool
 where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SET_SCALE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    ext.set_scale(scale)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.set_scale", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.set_scale");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        ext.set_scale(main_thread, scale)
+    })
 }
 unsafe extern "C" fn get_size<'host, P, E>(
     plugin: *const clap_plugin,
@@ -318,32 +358,35 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_GET_SIZE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(PluginGUIWindowSize { width, height }) = ext.get_size() else {
-        println!("Could not get size");
-        return false;
-    };
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.get_size", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.get_size");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(PluginGUIWindowSize { width, height }) = ext.get_size(main_thread) else {
+            return false;
+        };
 
-    unsafe {
-        *out_width = width;
-        *out_height = height;
-    };
+        unsafe {
+            *out_width = width;
+            *out_height = height;
+        };
 
-    true
+        true
+    })
 }
 unsafe extern "C" fn can_resize<'host, P, E>(plugin: *const clap_plugin) -> bool
 where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_GET_SIZE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    ext.can_resize()
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.can_resize", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.can_resize");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        ext.can_resize(main_thread)
+    })
 }
 unsafe extern "C" fn get_resize_hints<'host, P, E>(
     plugin: *const clap_plugin,
@@ -353,18 +396,19 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_GET_RESIZE_HINTS");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(hints) = ext.get_resize_hints() else {
-        println!("Failed to get hints");
-        return false;
-    };
-    unsafe {
-        *out_hints = hints;
-    }
-    true
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.get_resize_hints", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.get_resize_hints");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(hints) = ext.get_resize_hints(main_thread) else {
+            return false;
+        };
+        unsafe {
+            *out_hints = hints;
+        }
+        true
+    })
 }
 unsafe extern "C" fn adjust_size<'host, P, E>(
     plugin: *const clap_plugin,
@@ -375,23 +419,25 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_ADJUST_SIZE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let mut window_size: PluginGUIWindowSize = PluginGUIWindowSize {
-        width: unsafe { *width },
-        height: unsafe { *height },
-    };
-    if ext.adjust_size(&mut window_size) {
-        unsafe {
-            *width = window_size.width;
-            *height = window_size.height;
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.adjust_size", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.adjust_size");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let mut window_size: PluginGUIWindowSize = PluginGUIWindowSize {
+            width: unsafe { *width },
+            height: unsafe { *height },
+        };
+        if ext.adjust_size(main_thread, &mut window_size) {
+            unsafe {
+                *width = window_size.width;
+                *height = window_size.height;
+            }
+            true
+        } else {
+            false
         }
-        true
-    } else {
-        false
-    }
+    })
 }
 unsafe extern "C" fn set_size<'host, P, E>(
     plugin: *const clap_plugin,
@@ -402,11 +448,13 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SET_SIZE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    ext.set_size(PluginGUIWindowSize { width, height })
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.set_size", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.set_size");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        ext.set_size(main_thread, PluginGUIWindowSize { width, height })
+    })
 }
 unsafe extern "C" fn set_parent<'host, P, E>(
     plugin: *const clap_plugin,
@@ -416,15 +464,20 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SET_PARENT");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(parent) = (unsafe { window.as_ref() }) else {
-        println!("parent null");
-        return false;
-    };
-    ext.set_parent(parent)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.set_parent", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.set_parent");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(parent) = (unsafe { window.as_ref() }) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::HostMisbehaving,
+                format_args!("null parent window"),
+            );
+            return false;
+        };
+        ext.set_parent(main_thread, parent)
+    })
 }
 unsafe extern "C" fn set_transient<'host, P, E>(
     plugin: *const clap_plugin,
@@ -434,49 +487,60 @@ where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SET_TRANSIENT");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(transient) = (unsafe { window.as_ref() }) else {
-        println!("transient null");
-        return false;
-    };
-    ext.set_transient(transient)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.set_transient", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.set_transient");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(transient) = (unsafe { window.as_ref() }) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::HostMisbehaving,
+                format_args!("null transient window"),
+            );
+            return false;
+        };
+        ext.set_transient(main_thread, transient)
+    })
 }
 unsafe extern "C" fn suggest_title<'host, P, E>(plugin: *const clap_plugin, title: *const c_char)
 where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SUGGEST_TITLE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return;
-    };
-    let title = unsafe { PluginGUIWindowTitle::from_ptr(title) };
-    ext.suggest_title(title);
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.suggest_title", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.suggest_title");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return;
+        };
+        let title = unsafe { PluginGUIWindowTitle::from_ptr(title) };
+        ext.suggest_title(main_thread, title);
+    })
 }
 unsafe extern "C" fn show<'host, P, E>(plugin: *const clap_plugin) -> bool
 where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_SHOW");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    ext.show()
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.show", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.show");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        ext.show(main_thread)
+    })
 }
 unsafe extern "C" fn hide<'host, P, E>(plugin: *const clap_plugin) -> bool
 where
     P: HasExtension<'host, clap_plugin_gui, ExtensionType = E>,
     E: PluginGUIPrototype<'host, Base = clap_plugin_gui>,
 {
-    println!("GUI_HIDE");
-    let Some(ext) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    ext.hide()
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_gui.hide", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_gui.hide");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        ext.hide(main_thread)
+    })
 }
 pub const fn vtable<'host, P, E>() -> &'static clap_plugin_gui
 where
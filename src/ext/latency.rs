@@ -0,0 +1,147 @@
+//! Latency
+//!
+//! Reports the latency the plugin adds to the signal, like the lookahead of a
+//! limiter, so that the host can compensate for it.
+//!
+//! The latency may only change while the plugin is being activated, see
+//! [`latency_changed`] for telling the host about it at any other time.
+
+use crate::guard;
+use crate::host::ext::latency::HostLatency;
+use crate::plugin::instance::PluginInstance;
+use crate::plugin::{ActivationState, HasExtension};
+use crate::thread::{self, MainThread};
+
+use core::ffi::CStr;
+
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
+use clap_sys::{
+    ext::latency::{CLAP_EXT_LATENCY, clap_plugin_latency},
+    plugin::clap_plugin,
+};
+pub trait PluginLatencyPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_latency>
+{
+    /// `main-thread` & (`being-activated` | `active`)
+    ///
+    /// Returns the plugin latency in samples.
+    fn get(&self, main_thread: MainThread) -> u32;
+}
+impl PluginExtensionBase for clap_plugin_latency {
+    const ID: &'static CStr = CLAP_EXT_LATENCY;
+}
+
+/// `main-thread`
+///
+/// Tells the host that the latency of `plugin` changed, the way its activation allows:
+/// - while being activated, through `clap_host_latency.changed`
+/// - while active, by requesting a restart, the new latency is then reported
+///   on the next activation
+/// - while inactive, not at all, the host queries the latency on activation
+///
+/// Returns false if the host could not be told, either because `plugin` was not
+/// created by a factory, or it is being activated and the host lacks `clap_host_latency`.
+pub fn latency_changed<'host, P>(plugin: &P, main_thread: MainThread) -> bool
+where
+    P: HasExtension<'host, clap_plugin_latency>,
+{
+    let Some(instance) = PluginInstance::of(plugin) else {
+        return false;
+    };
+    let host = instance.host();
+    match instance.activation() {
+        ActivationState::Inactive => true,
+        ActivationState::BeingActivated => {
+            let Some(host_latency) = host.get_extension::<HostLatency>() else {
+                return false;
+            };
+            host_latency.changed(main_thread);
+            true
+        }
+        ActivationState::Active => {
+            host.request_restart();
+            true
+        }
+    }
+}
+
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_latency, ExtensionType = E> + 'ext,
+    E: PluginLatencyPrototype<'host, Parent = P, Base = clap_plugin_latency>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const E::Parent).as_ref() }?;
+    Some(parent.get_extension())
+}
+unsafe extern "C" fn get<'host, P, E>(plugin: *const clap_plugin) -> u32
+where
+    P: HasExtension<'host, clap_plugin_latency, ExtensionType = E>,
+    E: PluginLatencyPrototype<'host, Parent = P, Base = clap_plugin_latency>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_latency.get", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_latency.get");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return 0;
+        };
+        p.get(main_thread)
+    })
+}
+
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_latency
+where
+    P: HasExtension<'host, clap_plugin_latency, ExtensionType = E>,
+    E: PluginLatencyPrototype<'host, Parent = P, Base = clap_plugin_latency>,
+{
+    &clap_plugin_latency {
+        get: Some(get::<'host, P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_latency, ExtensionType = E>,
+    E: PluginLatencyPrototype<'host, Parent = P, Base = clap_plugin_latency>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbstractPrototype;
+    use crate::testing::{StubHost, TestPlugin};
+
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn changed_by_activation() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let instance = PluginInstance::<TestPlugin>::from_plugin(plugin).unwrap();
+        let main_thread = unsafe { MainThread::new_unchecked() };
+
+        test_plugin.latency.samples.store(64, Ordering::Relaxed);
+        let get = test_plugin.latency.as_base().get.unwrap();
+        assert_eq!(unsafe { get(plugin) }, 64);
+
+        assert!(latency_changed(test_plugin, main_thread));
+        assert_eq!(host.latency_changes.load(Ordering::Relaxed), 0);
+        assert_eq!(host.restart_requests.load(Ordering::Relaxed), 0);
+
+        instance.set_activation(ActivationState::BeingActivated);
+        assert!(latency_changed(test_plugin, main_thread));
+        assert_eq!(host.latency_changes.load(Ordering::Relaxed), 1);
+        assert_eq!(host.restart_requests.load(Ordering::Relaxed), 0);
+
+        instance.set_activation(ActivationState::Active);
+        assert!(latency_changed(test_plugin, main_thread));
+        assert_eq!(host.latency_changes.load(Ordering::Relaxed), 1);
+        assert_eq!(host.restart_requests.load(Ordering::Relaxed), 1);
+
+        instance.set_activation(ActivationState::Inactive);
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+}
@@ -0,0 +1,241 @@
+use core::ffi::CStr;
+use core::ops::BitOr;
+
+use crate::ext::{ExtensionPrototype, PluginExtensionBase, ProtoPtr, clap_flags};
+use crate::guard;
+use crate::log::Severity;
+use crate::plugin::instance::PluginInstance;
+use crate::plugin::{HasExtension, PluginName};
+use crate::thread::{self, MainThread};
+
+use clap_sys::ext::note_ports::{
+    CLAP_EXT_NOTE_PORTS, CLAP_NOTE_DIALECT_CLAP, CLAP_NOTE_DIALECT_MIDI,
+    CLAP_NOTE_DIALECT_MIDI_MPE, CLAP_NOTE_DIALECT_MIDI2, clap_note_dialect, clap_note_port_info,
+    clap_plugin_note_ports,
+};
+use clap_sys::id::clap_id;
+use clap_sys::plugin::clap_plugin;
+
+/// A single note dialect, the preferred one of a port
+#[repr(u32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteDialect {
+    /// `clap_event_note` and `clap_event_note_expression`
+    Clap = CLAP_NOTE_DIALECT_CLAP,
+    /// `clap_event_midi`, no polyphonic expression
+    Midi = CLAP_NOTE_DIALECT_MIDI,
+    /// `clap_event_midi`, with polyphonic expression (MPE)
+    MidiMpe = CLAP_NOTE_DIALECT_MIDI_MPE,
+    /// `clap_event_midi2`
+    Midi2 = CLAP_NOTE_DIALECT_MIDI2,
+}
+impl From<NoteDialect> for clap_note_dialect {
+    #[inline]
+    fn from(value: NoteDialect) -> Self {
+        value as clap_note_dialect
+    }
+}
+
+clap_flags! {
+    /// The set of note dialects a port supports
+    NoteDialects(clap_note_dialect) {
+        CLAP = CLAP_NOTE_DIALECT_CLAP;
+        MIDI = CLAP_NOTE_DIALECT_MIDI;
+        MIDI_MPE = CLAP_NOTE_DIALECT_MIDI_MPE;
+        MIDI2 = CLAP_NOTE_DIALECT_MIDI2;
+    }
+}
+impl From<NoteDialect> for NoteDialects {
+    #[inline]
+    fn from(value: NoteDialect) -> Self {
+        NoteDialects(value.into())
+    }
+}
+impl BitOr<NoteDialect> for NoteDialects {
+    type Output = NoteDialects;
+    #[inline]
+    fn bitor(self, rhs: NoteDialect) -> NoteDialects {
+        self.union(rhs.into())
+    }
+}
+
+/// Describes a note port, see `clap_note_port_info`
+pub struct NotePortInfo<'info> {
+    /// Stable identifier of the port
+    pub id: clap_id,
+    pub supported_dialects: NoteDialects,
+    /// Must be one of the supported dialects, the port is refused otherwise
+    pub preferred_dialect: NoteDialect,
+    pub name: &'info PluginName,
+}
+impl NotePortInfo<'_> {
+    pub fn to_raw(&self) -> clap_note_port_info {
+        clap_note_port_info {
+            id: self.id,
+            supported_dialects: self.supported_dialects.bits(),
+            preferred_dialect: self.preferred_dialect.into(),
+            name: self.name.to_fixed(),
+        }
+    }
+}
+
+pub trait PluginNotePortsPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_note_ports>
+{
+    /// `main-thread`
+    ///
+    /// Number of note ports, for either input or output.
+    fn count(&self, main_thread: MainThread, is_input: bool) -> u32;
+    /// `main-thread`
+    ///
+    /// Info about the note port at `index`.
+    fn get(&self, main_thread: MainThread, index: u32, is_input: bool) -> Option<NotePortInfo<'_>>;
+}
+
+impl PluginExtensionBase for clap_plugin_note_ports {
+    const ID: &'static CStr = CLAP_EXT_NOTE_PORTS;
+}
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_note_ports, ExtensionType = E> + 'ext,
+    E: PluginNotePortsPrototype<'host>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const P).as_ref() }?;
+    Some(parent.get_extension())
+}
+#[inline(never)]
+unsafe extern "C" fn count<'host, P, E>(plugin: *const clap_plugin, is_input: bool) -> u32
+where
+    P: HasExtension<'host, clap_plugin_note_ports, ExtensionType = E>,
+    E: PluginNotePortsPrototype<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_note_ports.count", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_note_ports.count");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return 0;
+        };
+        ext.count(main_thread, is_input)
+    })
+}
+#[inline(never)]
+unsafe extern "C" fn get<'host, P, E>(
+    plugin: *const clap_plugin,
+    index: u32,
+    is_input: bool,
+    info_dst: *mut clap_note_port_info,
+) -> bool
+where
+    P: HasExtension<'host, clap_plugin_note_ports, ExtensionType = E>,
+    E: PluginNotePortsPrototype<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_note_ports.get", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_note_ports.get");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(info) = ext.get(main_thread, index, is_input) else {
+            return false;
+        };
+        if !info
+            .supported_dialects
+            .contains(info.preferred_dialect.into())
+        {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::PluginMisbehaving,
+                format_args!(
+                    "note port {index}: preferred dialect {:?} is not supported",
+                    info.preferred_dialect
+                ),
+            );
+            return false;
+        }
+        let Some(info_dst) = (unsafe { info_dst.as_mut() }) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::HostMisbehaving,
+                format_args!("clap_plugin_note_ports.get: null info"),
+            );
+            return false;
+        };
+        *info_dst = info.to_raw();
+        true
+    })
+}
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_note_ports
+where
+    P: HasExtension<'host, clap_plugin_note_ports, ExtensionType = E>,
+    E: PluginNotePortsPrototype<'host>,
+{
+    &clap_plugin_note_ports {
+        count: Some(count::<P, E>),
+        get: Some(get::<P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_note_ports, ExtensionType = E>,
+    E: PluginNotePortsPrototype<'host, Parent = P>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbstractPrototype;
+    use crate::testing::{StubHost, TestNotePorts, TestPlugin};
+
+    #[test]
+    fn dialects() {
+        let dialects = NoteDialects::CLAP | NoteDialect::MidiMpe;
+        assert!(dialects.contains(NoteDialect::Clap.into()));
+        assert!(dialects.contains(NoteDialect::MidiMpe.into()));
+        assert!(!dialects.contains(NoteDialect::Midi.into()));
+        assert_eq!(
+            dialects.bits(),
+            CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI_MPE
+        );
+    }
+
+    #[test]
+    fn get_port() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let get = test_plugin.note_ports.as_base().get.unwrap();
+        let mut info: clap_note_port_info = unsafe { ::core::mem::zeroed() };
+        unsafe {
+            assert!(get(plugin, 0, true, &mut info));
+            assert_eq!(info.id, TestNotePorts::ID);
+            assert_eq!(
+                info.supported_dialects,
+                TestNotePorts::SUPPORTED_DIALECTS.bits()
+            );
+            assert_eq!(info.preferred_dialect, CLAP_NOTE_DIALECT_CLAP);
+            assert_eq!(CStr::from_ptr(info.name.as_ptr()), c"notes");
+            // No such port
+            assert!(!get(plugin, 0, false, &mut info));
+            assert!(!get(plugin, 0, true, ::core::ptr::null_mut()));
+        }
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+
+    #[test]
+    fn refuses_unsupported_preferred_dialect() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let get = test_plugin.note_ports.as_base().get.unwrap();
+        *test_plugin.note_ports.preferred_dialect.lock().unwrap() = NoteDialect::Midi2;
+        let mut info: clap_note_port_info = unsafe { ::core::mem::zeroed() };
+        info.id = 42;
+        unsafe {
+            assert!(!get(plugin, 0, true, &mut info));
+            (*plugin).destroy.unwrap()(plugin);
+        }
+        // Left as the host handed it over
+        assert_eq!(info.id, 42);
+    }
+}
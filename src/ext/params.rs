@@ -1,25 +1,35 @@
+use crate::events::{InputEvents, OutputEvents};
+use crate::guard;
 use crate::plugin::HasExtension;
 use crate::plugin::PluginParameterValueText;
+use crate::thread::{self, MainThread};
 
-use super::{ExtensionPrototype, ProtoPtr};
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
 pub use clap_proc_tools::plugin_parameter;
 use clap_sys::{
     events::{clap_input_events, clap_output_events},
-    ext::params::{clap_param_info, clap_plugin_params},
+    ext::params::{CLAP_EXT_PARAMS, clap_param_info, clap_plugin_params},
     id::clap_id,
     plugin::clap_plugin,
 };
-use core::ffi::c_char;
+use core::ffi::{CStr, c_char};
 pub trait PluginParamsPrototype<'host>:
     ExtensionPrototype<'host, Base = clap_plugin_params>
 {
-    fn count(&self) -> u32;
-    fn get_info(&self, param_index: u32) -> Option<&clap_param_info>;
-    fn get_value(&self, param_id: clap_id) -> Option<f64>;
+    fn count(&self, main_thread: MainThread) -> u32;
+    fn get_info(&self, main_thread: MainThread, param_index: u32) -> Option<&clap_param_info>;
+    fn get_value(&self, main_thread: MainThread, param_id: clap_id) -> Option<f64>;
     /// Takes in a mutable reference to a slice to write to
-    fn value_to_text(&self, param_id: clap_id, value: f64, dst: &mut [i8]) -> Option<usize>;
+    fn value_to_text(
+        &self,
+        main_thread: MainThread,
+        param_id: clap_id,
+        value: f64,
+        dst: &mut [i8],
+    ) -> Option<usize>;
     fn text_to_value(
         &self,
+        main_thread: MainThread,
         param_id: clap_id,
         param_value_text: &PluginParameterValueText,
     ) -> Option<f64>;
@@ -32,7 +42,10 @@ pub trait PluginParamsPrototype<'host>:
     /// parameter update (bi-directional), so a call to flush isn't required, also be aware
     /// that the plugin may use the sample offset in process(), while this information would be
     /// lost within flush().
-    fn flush(&self, in_events: &clap_input_events, out_events: &clap_output_events) -> Option<()>;
+    fn flush(&self, in_events: InputEvents<'_>, out_events: OutputEvents<'_>) -> Option<()>;
+}
+impl PluginExtensionBase for clap_plugin_params {
+    const ID: &'static CStr = CLAP_EXT_PARAMS;
 }
 fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
 where
@@ -49,10 +62,13 @@ where
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
-        return 0;
-    };
-    plugin.count()
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.count", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_params.count");
+        let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
+            return 0;
+        };
+        plugin.count(main_thread)
+    })
 }
 unsafe extern "C" fn get_info<'host, P, E>(
     plugin_ptr: *const clap_plugin,
@@ -63,14 +79,17 @@ where
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
-        return false;
-    };
-    if let Some(info) = plugin.get_info(param_index) {
-        unsafe { core::ptr::copy_nonoverlapping(info, param_info_ptr, 1) };
-        return true;
-    }
-    false
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.get_info", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_params.get_info");
+        let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
+            return false;
+        };
+        if let Some(info) = plugin.get_info(main_thread, param_index) {
+            unsafe { core::ptr::copy_nonoverlapping(info, param_info_ptr, 1) };
+            return true;
+        }
+        false
+    })
 }
 unsafe extern "C" fn get_value<'host, P, E>(
     plugin_ptr: *const clap_plugin,
@@ -81,14 +100,17 @@ where
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
-        return false;
-    };
-    if let Some(value) = plugin.get_value(param_id) {
-        unsafe { core::ptr::write(out_value, value) };
-        return true;
-    };
-    false
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.get_value", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_params.get_value");
+        let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
+            return false;
+        };
+        if let Some(value) = plugin.get_value(main_thread, param_id) {
+            unsafe { core::ptr::write(out_value, value) };
+            return true;
+        };
+        false
+    })
 }
 unsafe extern "C" fn value_to_text<'host, P, E>(
     plugin_ptr: *const clap_plugin,
@@ -101,14 +123,18 @@ where
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
-        return false;
-    };
-    let dst = unsafe { core::slice::from_raw_parts_mut(out_buffer, out_buffer_capacity as usize) };
-    if let Some(value_size) = plugin.value_to_text(param_id, value, dst) {
-        return value_size <= out_buffer_capacity as usize;
-    };
-    false
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.value_to_text", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_params.value_to_text");
+        let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
+            return false;
+        };
+        let dst =
+            unsafe { core::slice::from_raw_parts_mut(out_buffer, out_buffer_capacity as usize) };
+        if let Some(value_size) = plugin.value_to_text(main_thread, param_id, value, dst) {
+            return value_size <= out_buffer_capacity as usize;
+        };
+        false
+    })
 }
 unsafe extern "C" fn text_to_value<'host, P, E>(
     plugin_ptr: *const clap_plugin,
@@ -120,15 +146,18 @@ where
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
-        return false;
-    };
-    let param_value_text = unsafe { PluginParameterValueText::from_ptr(param_value_text) };
-    if let Some(value) = plugin.text_to_value(param_id, param_value_text) {
-        unsafe { core::ptr::write(out_value, value) };
-        return true;
-    };
-    false
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.text_to_value", || {
+        let main_thread = thread::main_thread::<P>(plugin_ptr, "clap_plugin_params.text_to_value");
+        let Some(plugin) = get_ext::<P, E>(plugin_ptr) else {
+            return false;
+        };
+        let param_value_text = unsafe { PluginParameterValueText::from_ptr(param_value_text) };
+        if let Some(value) = plugin.text_to_value(main_thread, param_id, param_value_text) {
+            unsafe { core::ptr::write(out_value, value) };
+            return true;
+        };
+        false
+    })
 }
 unsafe extern "C" fn flush<'host, P, E>(
     plugin_ptr: *const clap_plugin,
@@ -138,17 +167,20 @@ unsafe extern "C" fn flush<'host, P, E>(
     P: HasExtension<'host, clap_plugin_params, ExtensionType = E>,
     E: PluginParamsPrototype<'host, Parent = P>,
 {
-    let Some(parent) = (unsafe { plugin_ptr.as_ref() }) else {
-        return;
-    };
-    let Some(parent) = (unsafe { (parent.plugin_data as *const P).as_ref() }) else {
-        return;
-    };
-    let plugin = parent.get_extension();
-    let Some((in_, out)) = (unsafe { in_.as_ref() }).zip(unsafe { out.as_ref() }) else {
-        return;
-    };
-    plugin.flush(in_, out).unwrap_or(())
+    guard::plugin_call::<P, _, _>(plugin_ptr, "clap_plugin_params.flush", || {
+        let Some(parent) = (unsafe { plugin_ptr.as_ref() }) else {
+            return;
+        };
+        let Some(parent) = (unsafe { (parent.plugin_data as *const P).as_ref() }) else {
+            return;
+        };
+        let plugin = parent.get_extension();
+        let Some((in_, out)) = (unsafe { in_.as_ref() }).zip(unsafe { out.as_ref() }) else {
+            return;
+        };
+        let (in_, out) = unsafe { (InputEvents::from_raw(in_), OutputEvents::from_raw(out)) };
+        plugin.flush(in_, out).unwrap_or(())
+    })
 }
 pub const fn vtable<'host, P, E>() -> &'static clap_plugin_params
 where
@@ -0,0 +1,113 @@
+//! POSIX fd support
+//!
+//! Lets the plugin have its file descriptors polled by the host's event loop,
+//! like the connection of an X11 or Wayland GUI, so that it needs no thread of
+//! its own. The file descriptors are registered with
+//! [`HostPosixFdSupport`](crate::host::ext::posix_fd_support::HostPosixFdSupport),
+//! and reported ready in [`PluginPosixFdSupportPrototype::on_fd`].
+
+use core::ffi::CStr;
+
+use crate::ext::{ExtensionPrototype, PluginExtensionBase, ProtoPtr, clap_flags};
+use crate::guard;
+use crate::plugin::HasExtension;
+use crate::thread::{self, MainThread};
+
+use clap_sys::ext::posix_fd_support::{
+    CLAP_EXT_POSIX_FD_SUPPORT, CLAP_POSIX_FD_ERROR, CLAP_POSIX_FD_READ, CLAP_POSIX_FD_WRITE,
+    clap_plugin_posix_fd_support, clap_posix_fd_flags,
+};
+use clap_sys::plugin::clap_plugin;
+
+clap_flags! {
+    /// What a file descriptor is polled for, or was found ready for
+    PosixFdFlags(clap_posix_fd_flags) {
+        READ = CLAP_POSIX_FD_READ;
+        WRITE = CLAP_POSIX_FD_WRITE;
+        ERROR = CLAP_POSIX_FD_ERROR;
+    }
+}
+
+pub trait PluginPosixFdSupportPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_posix_fd_support>
+{
+    /// `main-thread`
+    ///
+    /// The file descriptor `fd` is ready for what `flags` tell.
+    fn on_fd(&self, main_thread: MainThread, fd: i32, flags: PosixFdFlags);
+}
+
+impl PluginExtensionBase for clap_plugin_posix_fd_support {
+    const ID: &'static CStr = CLAP_EXT_POSIX_FD_SUPPORT;
+}
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_posix_fd_support, ExtensionType = E> + 'ext,
+    E: PluginPosixFdSupportPrototype<'host>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const P).as_ref() }?;
+    Some(parent.get_extension())
+}
+#[inline(never)]
+unsafe extern "C" fn on_fd<'host, P, E>(
+    plugin: *const clap_plugin,
+    fd: i32,
+    flags: clap_posix_fd_flags,
+) where
+    P: HasExtension<'host, clap_plugin_posix_fd_support, ExtensionType = E>,
+    E: PluginPosixFdSupportPrototype<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_posix_fd_support.on_fd", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_posix_fd_support.on_fd");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return;
+        };
+        ext.on_fd(main_thread, fd, PosixFdFlags::from_bits_truncate(flags))
+    })
+}
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_posix_fd_support
+where
+    P: HasExtension<'host, clap_plugin_posix_fd_support, ExtensionType = E>,
+    E: PluginPosixFdSupportPrototype<'host>,
+{
+    &clap_plugin_posix_fd_support {
+        on_fd: Some(on_fd::<P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_posix_fd_support, ExtensionType = E>,
+    E: PluginPosixFdSupportPrototype<'host, Parent = P>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbstractPrototype;
+    use crate::testing::{StubHost, TestPlugin};
+
+    #[test]
+    fn on_fd_drops_unknown_bits() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let on_fd = test_plugin.fds.as_base().on_fd.unwrap();
+        unsafe {
+            on_fd(plugin, 3, CLAP_POSIX_FD_READ | CLAP_POSIX_FD_ERROR | 1 << 7);
+            on_fd(plugin, 4, 1 << 31);
+        }
+        assert_eq!(
+            *test_plugin.fds.ready.lock().unwrap(),
+            [
+                (3, PosixFdFlags::READ | PosixFdFlags::ERROR),
+                (4, PosixFdFlags::empty()),
+            ]
+        );
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+}
@@ -0,0 +1,79 @@
+//! Extension discovery
+//!
+//! Backs `#[derive(ExtensionRegistry)]`, which answers the host's
+//! `clap_plugin.get_extension(id)` by probing every extension base this crate
+//! knows about, along with those listed in `#[extensions(...)]`, and returning
+//! the vtable of those the plugin has a [`HasExtension`] implementation for.
+//!
+//! The probing relies on method resolution preferring [`ProvidedExtension`],
+//! which only applies when the plugin implements [`HasExtension`] for the
+//! probed base, over [`MissingExtension`], which always applies one auto-ref
+//! further away. This only works on concrete types, which is why it is driven
+//! by a derive rather than a blanket implementation.
+
+use core::ffi::c_void;
+use core::marker::PhantomData;
+
+use crate::AbstractPrototype;
+use crate::ext::PluginExtensionBase;
+use crate::plugin::{HasExtension, PluginExtensionID};
+
+pub struct ExtensionProbe<'plugin, P, B>(&'plugin P, PhantomData<B>);
+impl<'plugin, P, B> ExtensionProbe<'plugin, P, B> {
+    #[inline]
+    pub const fn new(plugin: &'plugin P) -> Self {
+        ExtensionProbe(plugin, PhantomData)
+    }
+}
+pub trait ProvidedExtension<'host> {
+    fn probe(&self, id: &PluginExtensionID) -> Option<*const c_void>;
+}
+impl<'host, P, B> ProvidedExtension<'host> for ExtensionProbe<'_, P, B>
+where
+    P: HasExtension<'host, B>,
+    B: PluginExtensionBase + 'host,
+{
+    #[inline]
+    fn probe(&self, id: &PluginExtensionID) -> Option<*const c_void> {
+        if !B::matches(id) {
+            return None;
+        }
+        let base: *const B = self.0.get_extension().as_base();
+        Some(base as *const c_void)
+    }
+}
+pub trait MissingExtension {
+    #[inline]
+    fn probe(&self, _id: &PluginExtensionID) -> Option<*const c_void> {
+        None
+    }
+}
+impl<P, B> MissingExtension for &ExtensionProbe<'_, P, B> {}
+
+#[cfg(test)]
+mod tests {
+    use clap_sys::ext::timer_support::clap_plugin_timer_support;
+
+    use crate::AbstractPrototype;
+    use crate::ext::{ExtensionRegistry, PluginExtensionBase};
+    use crate::plugin::PluginExtensionID;
+    use crate::testing::{StubHost, TestPlugin, clap_plugin_draft};
+
+    #[test]
+    fn derived_lookup() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let lookup = |id| test_plugin.lookup(PluginExtensionID::from_c_str(id));
+
+        let timers = lookup(clap_plugin_timer_support::ID);
+        assert_eq!(timers, Some(test_plugin.timers.as_base() as *const _ as _));
+        let draft = lookup(clap_plugin_draft::ID);
+        assert_eq!(draft, Some(&test_plugin.draft.base as *const _ as _));
+        assert_eq!(lookup(clap_plugin_draft::COMPAT_IDS[0]), draft);
+        assert_eq!(lookup(c"com.example.unknown"), None);
+        // Known to the crate, not implemented by the plugin
+        assert_eq!(lookup(clap_sys::ext::gui::CLAP_EXT_GUI), None);
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+}
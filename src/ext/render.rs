@@ -0,0 +1,203 @@
+//! Render
+//!
+//! Tells the plugin whether it is processing in realtime or offline, like when
+//! bouncing, so that it may switch to costlier algorithms that could not keep up
+//! in realtime.
+
+use crate::guard;
+use crate::log::Severity;
+use crate::plugin::HasExtension;
+use crate::plugin::instance::PluginInstance;
+use crate::thread::{self, MainThread};
+
+use core::ffi::CStr;
+
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
+use clap_sys::{
+    ext::render::{
+        CLAP_EXT_RENDER, CLAP_RENDER_OFFLINE, CLAP_RENDER_REALTIME, clap_plugin_render,
+        clap_plugin_render_mode,
+    },
+    plugin::clap_plugin,
+};
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RenderMode {
+    /// Processing is done in realtime, the default
+    #[default]
+    Realtime = CLAP_RENDER_REALTIME,
+    /// Processing is done as fast as possible, or as slow as needed
+    Offline = CLAP_RENDER_OFFLINE,
+}
+impl From<RenderMode> for clap_plugin_render_mode {
+    #[inline]
+    fn from(value: RenderMode) -> Self {
+        value as clap_plugin_render_mode
+    }
+}
+impl TryFrom<clap_plugin_render_mode> for RenderMode {
+    type Error = clap_plugin_render_mode;
+    fn try_from(value: clap_plugin_render_mode) -> Result<Self, Self::Error> {
+        match value {
+            CLAP_RENDER_REALTIME => Ok(RenderMode::Realtime),
+            CLAP_RENDER_OFFLINE => Ok(RenderMode::Offline),
+            _ => Err(value),
+        }
+    }
+}
+
+pub trait PluginRenderPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_render>
+{
+    /// `main-thread`
+    ///
+    /// Returns true if the plugin has a hard requirement to process in realtime,
+    /// like when it depends on external hardware.
+    fn has_hard_realtime_requirement(&self, main_thread: MainThread) -> bool;
+    /// `main-thread`
+    ///
+    /// Switches the render mode, returns true if the plugin could.
+    fn set(&self, main_thread: MainThread, mode: RenderMode) -> bool;
+}
+impl PluginExtensionBase for clap_plugin_render {
+    const ID: &'static CStr = CLAP_EXT_RENDER;
+}
+
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_render, ExtensionType = E> + 'ext,
+    E: PluginRenderPrototype<'host, Parent = P, Base = clap_plugin_render>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const E::Parent).as_ref() }?;
+    Some(parent.get_extension())
+}
+unsafe extern "C" fn has_hard_realtime_requirement<'host, P, E>(plugin: *const clap_plugin) -> bool
+where
+    P: HasExtension<'host, clap_plugin_render, ExtensionType = E>,
+    E: PluginRenderPrototype<'host, Parent = P, Base = clap_plugin_render>,
+{
+    guard::plugin_call::<P, _, _>(
+        plugin,
+        "clap_plugin_render.has_hard_realtime_requirement",
+        || {
+            let main_thread = thread::main_thread::<P>(
+                plugin,
+                "clap_plugin_render.has_hard_realtime_requirement",
+            );
+            let Some(p) = get_ext::<P, E>(plugin) else {
+                return false;
+            };
+            p.has_hard_realtime_requirement(main_thread)
+        },
+    )
+}
+unsafe extern "C" fn set<'host, P, E>(
+    plugin: *const clap_plugin,
+    mode: clap_plugin_render_mode,
+) -> bool
+where
+    P: HasExtension<'host, clap_plugin_render, ExtensionType = E>,
+    E: PluginRenderPrototype<'host, Parent = P, Base = clap_plugin_render>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_render.set", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_render.set");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Ok(mode) = RenderMode::try_from(mode) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::HostMisbehaving,
+                format_args!("clap_plugin_render.set: unknown render mode {mode}"),
+            );
+            return false;
+        };
+        p.set(main_thread, mode)
+    })
+}
+
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_render
+where
+    P: HasExtension<'host, clap_plugin_render, ExtensionType = E>,
+    E: PluginRenderPrototype<'host, Parent = P, Base = clap_plugin_render>,
+{
+    &clap_plugin_render {
+        has_hard_realtime_requirement: Some(has_hard_realtime_requirement::<'host, P, E>),
+        set: Some(set::<'host, P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_render, ExtensionType = E>,
+    E: PluginRenderPrototype<'host, Parent = P, Base = clap_plugin_render>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{StubHost, TestPlugin};
+
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn render_mode() {
+        assert_eq!(
+            RenderMode::try_from(CLAP_RENDER_REALTIME),
+            Ok(RenderMode::Realtime)
+        );
+        assert_eq!(
+            RenderMode::try_from(CLAP_RENDER_OFFLINE),
+            Ok(RenderMode::Offline)
+        );
+        assert_eq!(RenderMode::try_from(2), Err(2));
+        assert_eq!(
+            clap_plugin_render_mode::from(RenderMode::Offline),
+            CLAP_RENDER_OFFLINE
+        );
+    }
+
+    #[test]
+    fn set_and_hard_realtime() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let render = unsafe {
+            &*((*plugin).get_extension.unwrap()(plugin, CLAP_EXT_RENDER.as_ptr())
+                as *const clap_plugin_render)
+        };
+        let has_hard_realtime_requirement = render.has_hard_realtime_requirement.unwrap();
+        let set = render.set.unwrap();
+        unsafe {
+            assert!(!has_hard_realtime_requirement(plugin));
+            assert!(set(plugin, CLAP_RENDER_OFFLINE));
+            assert_eq!(
+                *test_plugin.render.mode.lock().unwrap(),
+                RenderMode::Offline
+            );
+            // Unknown modes are refused before reaching the plugin
+            assert!(!set(plugin, 2));
+            assert_eq!(
+                *test_plugin.render.mode.lock().unwrap(),
+                RenderMode::Offline
+            );
+
+            test_plugin
+                .render
+                .hard_realtime
+                .store(true, Ordering::Relaxed);
+            assert!(has_hard_realtime_requirement(plugin));
+            assert!(set(plugin, CLAP_RENDER_REALTIME));
+            assert!(!set(plugin, CLAP_RENDER_OFFLINE));
+            assert_eq!(
+                *test_plugin.render.mode.lock().unwrap(),
+                RenderMode::Realtime
+            );
+            (*plugin).destroy.unwrap()(plugin);
+        }
+    }
+}
@@ -11,12 +11,16 @@
 //! instance, for saving/loading a plugin preset or while saving/loading the project
 //! then consider implementing CLAP_EXT_STATE_CONTEXT in addition to CLAP_EXT_STATE
 
+use crate::guard;
 use crate::plugin::HasExtension;
+use crate::thread::{self, MainThread};
 
-use super::{ExtensionPrototype, ProtoPtr};
+use core::ffi::CStr;
+
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
 pub use clap_proc_tools::plugin_parameter;
 use clap_sys::{
-    ext::state::clap_plugin_state,
+    ext::state::{CLAP_EXT_STATE, clap_plugin_state},
     plugin::clap_plugin,
     stream::{clap_istream, clap_ostream},
 };
@@ -25,12 +29,15 @@ pub trait PluginStatePrototype<'host>: ExtensionPrototype<'host, Base = clap_plu
     ///
     /// Saves the plugin state into stream.
     /// Returns true if the state was correctly saved.
-    fn save(&self, output_stream: &clap_ostream) -> bool;
+    fn save(&self, main_thread: MainThread, output_stream: &clap_ostream) -> bool;
     /// `main-thread`
     ///
     /// Loads the plugin state from stream.
     /// Returns true if the state was correctly restored.
-    fn load(&self, input_stream: &clap_istream) -> bool;
+    fn load(&self, main_thread: MainThread, input_stream: &clap_istream) -> bool;
+}
+impl PluginExtensionBase for clap_plugin_state {
+    const ID: &'static CStr = CLAP_EXT_STATE;
 }
 fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
 where
@@ -50,13 +57,16 @@ where
     P: HasExtension<'host, clap_plugin_state, ExtensionType = E>,
     E: PluginStatePrototype<'host, Parent = P, Base = clap_plugin_state>,
 {
-    let Some(p) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(output_stream) = (unsafe { output_stream.as_ref() }) else {
-        return false;
-    };
-    p.save(output_stream)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_state.save", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_state.save");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(output_stream) = (unsafe { output_stream.as_ref() }) else {
+            return false;
+        };
+        p.save(main_thread, output_stream)
+    })
 }
 unsafe extern "C" fn load<'host, P, E>(
     plugin: *const clap_plugin,
@@ -66,13 +76,16 @@ where
     P: HasExtension<'host, clap_plugin_state, ExtensionType = E>,
     E: PluginStatePrototype<'host, Parent = P, Base = clap_plugin_state>,
 {
-    let Some(p) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(input_stream) = (unsafe { input_stream.as_ref() }) else {
-        return false;
-    };
-    p.load(input_stream)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_state.load", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_state.load");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(input_stream) = (unsafe { input_stream.as_ref() }) else {
+            return false;
+        };
+        p.load(main_thread, input_stream)
+    })
 }
 
 pub const fn vtable<'host, P, E>() -> &'static clap_plugin_state
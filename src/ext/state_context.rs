@@ -24,17 +24,20 @@
 
 pub use clap_proc_tools::plugin_parameter;
 
+use core::ffi::CStr;
 use core::mem::transmute;
 
 use crate::ProtoPtr;
+use crate::guard;
 use crate::plugin::HasExtension;
+use crate::thread::{self, MainThread};
 
-use crate::ext::ExtensionPrototype;
+use crate::ext::{ExtensionPrototype, PluginExtensionBase};
 
 use clap_sys::plugin::clap_plugin;
 
-use clap_sys::ext::state_context::clap_plugin_state_context;
 use clap_sys::ext::state_context::clap_plugin_state_context_type;
+use clap_sys::ext::state_context::{CLAP_EXT_STATE_CONTEXT, clap_plugin_state_context};
 use clap_sys::ext::state_context::{
     CLAP_STATE_CONTEXT_FOR_DUPLICATE, CLAP_STATE_CONTEXT_FOR_PRESET, CLAP_STATE_CONTEXT_FOR_PROJECT,
 };
@@ -61,14 +64,27 @@ pub trait PluginStateContextPrototype<'host>:
     ///
     /// Note that the result may be loaded by both clap_plugin_state.load() and
     /// clap_plugin_state_context.load().
-    fn save(&self, output_stream: &clap_ostream, variant: PluginStateContextVariant) -> bool;
+    fn save(
+        &self,
+        main_thread: MainThread,
+        output_stream: &clap_ostream,
+        variant: PluginStateContextVariant,
+    ) -> bool;
     /// `main-thread`
     /// Loads the plugin state from stream, according to context_type.
     /// Returns true if the state was correctly restored.
     ///
     /// Note that the state may have been saved by clap_plugin_state.save() or
     /// clap_plugin_state_context.save() with a different context_type.
-    fn load(&self, input_stream: &clap_istream, variant: PluginStateContextVariant) -> bool;
+    fn load(
+        &self,
+        main_thread: MainThread,
+        input_stream: &clap_istream,
+        variant: PluginStateContextVariant,
+    ) -> bool;
+}
+impl PluginExtensionBase for clap_plugin_state_context {
+    const ID: &'static CStr = CLAP_EXT_STATE_CONTEXT;
 }
 fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
 where
@@ -76,7 +92,6 @@ where
     E: PluginStateContextPrototype<'host>,
     'host: 'ext,
 {
-    println!("PARAMS ACCESS");
     let plugin = unsafe { ptr.as_ref() }?;
     let parent = unsafe { (plugin.plugin_data as *const P).as_ref() }?;
     Some(parent.get_extension())
@@ -90,14 +105,17 @@ where
     P: HasExtension<'host, clap_plugin_state_context, ExtensionType = E>,
     E: PluginStateContextPrototype<'host>,
 {
-    let Some(p) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(output_stream) = (unsafe { output_stream.as_ref() }) else {
-        return false;
-    };
-    let variant: PluginStateContextVariant = unsafe { transmute(context_variant) };
-    p.save(output_stream, variant)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_state_context.save", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_state_context.save");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(output_stream) = (unsafe { output_stream.as_ref() }) else {
+            return false;
+        };
+        let variant: PluginStateContextVariant = unsafe { transmute(context_variant) };
+        p.save(main_thread, output_stream, variant)
+    })
 }
 unsafe extern "C" fn load<'host, P, E>(
     plugin: *const clap_plugin,
@@ -108,14 +126,17 @@ where
     P: HasExtension<'host, clap_plugin_state_context, ExtensionType = E>,
     E: PluginStateContextPrototype<'host>,
 {
-    let Some(p) = get_ext::<P, E>(plugin) else {
-        return false;
-    };
-    let Some(input_stream) = (unsafe { input_stream.as_ref() }) else {
-        return false;
-    };
-    let variant: PluginStateContextVariant = unsafe { transmute(context_variant) };
-    p.load(input_stream, variant)
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_state_context.load", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_state_context.load");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(input_stream) = (unsafe { input_stream.as_ref() }) else {
+            return false;
+        };
+        let variant: PluginStateContextVariant = unsafe { transmute(context_variant) };
+        p.load(main_thread, input_stream, variant)
+    })
 }
 
 pub const fn vtable<'host, P, E>() -> &'static clap_plugin_state_context
@@ -0,0 +1,167 @@
+//! Tail
+//!
+//! Reports how long the output keeps ringing after the input went silent,
+//! like the decay of a reverb or the feedback of a delay, so that the host
+//! keeps processing the plugin for that long.
+//!
+//! Returning [`ProcessResult::Tail`] from
+//! [`PluginPrototype::process`](crate::plugin::PluginPrototype::process) leaves it to the
+//! reported [`TailLength`] to decide when the plugin may sleep, see
+//! [`TailLength::quiet_result`].
+
+use crate::ProcessResult;
+use crate::guard;
+use crate::plugin::HasExtension;
+
+use core::ffi::CStr;
+
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
+use clap_sys::{
+    ext::tail::{CLAP_EXT_TAIL, clap_plugin_tail},
+    plugin::clap_plugin,
+};
+
+/// The length of the tail, the host treats anything at or above [`i32::MAX`] as infinite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TailLength {
+    /// Rings out after that many samples, clamped below [`i32::MAX`]
+    Samples(u32),
+    /// Never rings out, like a looper or an infinite reverb freeze
+    Infinite,
+}
+impl TailLength {
+    const INFINITE: u32 = i32::MAX as u32;
+
+    pub const fn from_raw(raw: u32) -> TailLength {
+        if raw >= Self::INFINITE {
+            TailLength::Infinite
+        } else {
+            TailLength::Samples(raw)
+        }
+    }
+    pub const fn to_raw(&self) -> u32 {
+        match *self {
+            TailLength::Samples(samples) if samples >= Self::INFINITE => Self::INFINITE - 1,
+            TailLength::Samples(samples) => samples,
+            TailLength::Infinite => Self::INFINITE,
+        }
+    }
+    #[inline]
+    pub const fn is_infinite(&self) -> bool {
+        matches!(self, TailLength::Infinite)
+    }
+    /// The result of a process call once the input went quiet: [`ProcessResult::Tail`]
+    /// while there is a tail for the host to play out, [`ProcessResult::Sleep`] when there is none.
+    #[inline]
+    pub const fn quiet_result(&self) -> ProcessResult {
+        match self {
+            TailLength::Samples(0) => ProcessResult::Sleep,
+            _ => ProcessResult::Tail,
+        }
+    }
+}
+impl From<TailLength> for u32 {
+    #[inline]
+    fn from(value: TailLength) -> Self {
+        value.to_raw()
+    }
+}
+
+pub trait PluginTailPrototype<'host>: ExtensionPrototype<'host, Base = clap_plugin_tail> {
+    /// `main-thread` | `audio-thread`
+    ///
+    /// Returns the tail length.
+    fn get(&self) -> TailLength;
+}
+impl PluginExtensionBase for clap_plugin_tail {
+    const ID: &'static CStr = CLAP_EXT_TAIL;
+}
+
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_tail, ExtensionType = E> + 'ext,
+    E: PluginTailPrototype<'host, Parent = P, Base = clap_plugin_tail>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const E::Parent).as_ref() }?;
+    Some(parent.get_extension())
+}
+unsafe extern "C" fn get<'host, P, E>(plugin: *const clap_plugin) -> u32
+where
+    P: HasExtension<'host, clap_plugin_tail, ExtensionType = E>,
+    E: PluginTailPrototype<'host, Parent = P, Base = clap_plugin_tail>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_tail.get", || {
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return 0;
+        };
+        p.get().to_raw()
+    })
+}
+
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_tail
+where
+    P: HasExtension<'host, clap_plugin_tail, ExtensionType = E>,
+    E: PluginTailPrototype<'host, Parent = P, Base = clap_plugin_tail>,
+{
+    &clap_plugin_tail {
+        get: Some(get::<'host, P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_tail, ExtensionType = E>,
+    E: PluginTailPrototype<'host, Parent = P, Base = clap_plugin_tail>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use clap_sys::process::{
+        CLAP_PROCESS_CONTINUE, CLAP_PROCESS_SLEEP, CLAP_PROCESS_TAIL, clap_process,
+    };
+
+    use super::*;
+    use crate::testing::{StubHost, TestPlugin};
+
+    #[test]
+    fn tail_length() {
+        assert_eq!(TailLength::from_raw(i32::MAX as u32), TailLength::Infinite);
+        assert_eq!(TailLength::from_raw(u32::MAX), TailLength::Infinite);
+        assert_eq!(TailLength::from_raw(48_000), TailLength::Samples(48_000));
+        assert_eq!(TailLength::Samples(u32::MAX).to_raw(), i32::MAX as u32 - 1);
+        assert_eq!(TailLength::Samples(0).quiet_result(), ProcessResult::Sleep);
+        assert_eq!(TailLength::Infinite.quiet_result(), ProcessResult::Tail);
+    }
+
+    #[test]
+    fn process_tail() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let raw = clap_process {
+            steady_time: -1,
+            frames_count: 0,
+            transport: ::core::ptr::null(),
+            audio_inputs: ::core::ptr::null(),
+            audio_outputs: ::core::ptr::null_mut(),
+            audio_inputs_count: 0,
+            audio_outputs_count: 0,
+            in_events: ::core::ptr::null(),
+            out_events: ::core::ptr::null(),
+        };
+        let process = || unsafe { (*plugin).process.unwrap()(plugin, &raw) };
+        assert_eq!(process(), CLAP_PROCESS_CONTINUE);
+
+        *test_plugin.process_result.lock().unwrap() = ProcessResult::Tail;
+        assert_eq!(process(), CLAP_PROCESS_TAIL);
+        *test_plugin.tail.length.lock().unwrap() = TailLength::Samples(64);
+        assert_eq!(process(), CLAP_PROCESS_TAIL);
+        *test_plugin.tail.length.lock().unwrap() = TailLength::Samples(0);
+        assert_eq!(process(), CLAP_PROCESS_SLEEP);
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+}
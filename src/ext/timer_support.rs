@@ -0,0 +1,280 @@
+//! Timer support
+//!
+//! Periodic `main-thread` callbacks from the host, for plugins which have no GUI
+//! event loop to drive them, like meters.
+//!
+//! A timer registered through [`HostTimerSupport`] directly is handled in
+//! [`PluginTimerSupportPrototype::on_timer`]. One registered through the
+//! [`TimerRegistry`] returned by [`PluginTimerSupportPrototype::timers`] runs its
+//! own callback instead, and is unregistered with the host once the plugin is destroyed.
+
+use std::sync::{Mutex, MutexGuard};
+
+use crate::guard;
+use crate::host::Host;
+use crate::host::ext::timer_support::HostTimerSupport;
+use crate::plugin::HasExtension;
+use crate::plugin::instance::PluginInstance;
+use crate::thread::{self, MainThread};
+
+use core::ffi::CStr;
+
+use super::{ExtensionPrototype, PluginExtensionBase, ProtoPtr};
+use clap_sys::{
+    ext::timer_support::{CLAP_EXT_TIMER_SUPPORT, clap_plugin_timer_support},
+    id::clap_id,
+    plugin::clap_plugin,
+};
+
+type TimerCallback = Box<dyn FnMut(MainThread) + Send>;
+
+struct Timer {
+    id: clap_id,
+    /// Taken out while running, so that the callback may register or unregister timers
+    callback: Option<TimerCallback>,
+}
+
+/// The timers of a plugin, along with the callback to run on each of them
+#[derive(Default)]
+pub struct TimerRegistry {
+    timers: Mutex<Vec<Timer>>,
+}
+impl TimerRegistry {
+    pub const fn new() -> TimerRegistry {
+        TimerRegistry {
+            timers: Mutex::new(Vec::new()),
+        }
+    }
+    /// `main-thread`
+    ///
+    /// Registers a timer with the host of `plugin`, which runs `callback` every
+    /// `period_ms` until unregistered, or until the plugin is destroyed.
+    /// The registry must be the one `plugin` returns from [`PluginTimerSupportPrototype::timers`].
+    ///
+    /// Returns the id of the timer, [`None`] if `plugin` was not created by a factory,
+    /// or the host lacks `clap_host_timer_support` or refused the timer.
+    pub fn register<'host, P, F>(
+        &self,
+        plugin: &P,
+        main_thread: MainThread,
+        period_ms: u32,
+        callback: F,
+    ) -> Option<clap_id>
+    where
+        P: HasExtension<'host, clap_plugin_timer_support>,
+        P::ExtensionType: PluginTimerSupportPrototype<'host>,
+        F: FnMut(MainThread) + Send + 'static,
+    {
+        let instance = PluginInstance::of(plugin)?;
+        let timer_support = instance.host().get_extension::<HostTimerSupport>()?;
+        let id = timer_support.register_timer(main_thread, period_ms)?;
+        self.lock().push(Timer {
+            id,
+            callback: Some(Box::new(callback)),
+        });
+        instance.on_teardown(
+            CLAP_EXT_TIMER_SUPPORT,
+            unregister_all::<'host, P, P::ExtensionType>,
+        );
+        Some(id)
+    }
+    /// `main-thread`
+    ///
+    /// Unregisters the timer `timer_id` with the host of `plugin`, and drops its callback.
+    ///
+    /// Returns false if the timer is not in the registry.
+    pub fn unregister<'host, P>(
+        &self,
+        plugin: &P,
+        main_thread: MainThread,
+        timer_id: clap_id,
+    ) -> bool
+    where
+        P: HasExtension<'host, clap_plugin_timer_support>,
+    {
+        let timer = {
+            let mut timers = self.lock();
+            let Some(position) = timers.iter().position(|timer| timer.id == timer_id) else {
+                return false;
+            };
+            timers.swap_remove(position)
+        };
+        if let Some(instance) = PluginInstance::of(plugin)
+            && let Some(timer_support) = instance.host().get_extension::<HostTimerSupport>()
+        {
+            timer_support.unregister_timer(main_thread, timer.id);
+        }
+        true
+    }
+    #[inline]
+    pub fn contains(&self, timer_id: clap_id) -> bool {
+        self.lock().iter().any(|timer| timer.id == timer_id)
+    }
+    /// Runs the callback of `timer_id`, returns false if the timer is not in the registry.
+    fn dispatch(&self, main_thread: MainThread, timer_id: clap_id) -> bool {
+        let callback = {
+            let mut timers = self.lock();
+            let Some(timer) = timers.iter_mut().find(|timer| timer.id == timer_id) else {
+                return false;
+            };
+            timer.callback.take()
+        };
+        // Already running, the host fired the timer from within its own callback
+        let Some(mut callback) = callback else {
+            return true;
+        };
+        callback(main_thread);
+        // Put back, unless the callback unregistered its own timer
+        if let Some(timer) = self.lock().iter_mut().find(|timer| timer.id == timer_id) {
+            timer.callback = Some(callback);
+        }
+        true
+    }
+    fn unregister_all(&self, host: Host<'_>, main_thread: MainThread) {
+        let timers = ::core::mem::take(&mut *self.lock());
+        let Some(timer_support) = host.get_extension::<HostTimerSupport>() else {
+            return;
+        };
+        for timer in timers {
+            timer_support.unregister_timer(main_thread, timer.id);
+        }
+    }
+    fn lock(&self) -> MutexGuard<'_, Vec<Timer>> {
+        self.timers
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+pub trait PluginTimerSupportPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_timer_support>
+{
+    /// `main-thread`
+    ///
+    /// Called by the host for every tick of the timer `timer_id`,
+    /// unless the timer is in [`PluginTimerSupportPrototype::timers`].
+    fn on_timer(&self, main_thread: MainThread, timer_id: clap_id);
+    /// The registry whose timers run their own callback, if any.
+    fn timers(&self) -> Option<&TimerRegistry> {
+        None
+    }
+}
+impl PluginExtensionBase for clap_plugin_timer_support {
+    const ID: &'static CStr = CLAP_EXT_TIMER_SUPPORT;
+}
+
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_timer_support, ExtensionType = E> + 'ext,
+    E: PluginTimerSupportPrototype<'host, Parent = P, Base = clap_plugin_timer_support>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const E::Parent).as_ref() }?;
+    Some(parent.get_extension())
+}
+unsafe extern "C" fn on_timer<'host, P, E>(plugin: *const clap_plugin, timer_id: clap_id)
+where
+    P: HasExtension<'host, clap_plugin_timer_support, ExtensionType = E>,
+    E: PluginTimerSupportPrototype<'host, Parent = P, Base = clap_plugin_timer_support>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_timer_support.on_timer", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_timer_support.on_timer");
+        let Some(p) = get_ext::<P, E>(plugin) else {
+            return;
+        };
+        if p.timers()
+            .is_some_and(|timers| timers.dispatch(main_thread, timer_id))
+        {
+            return;
+        }
+        p.on_timer(main_thread, timer_id)
+    })
+}
+/// Unregisters the timers left in the registry as the plugin is destroyed
+unsafe extern "C" fn unregister_all<'host, P, E>(plugin: *const clap_plugin)
+where
+    P: HasExtension<'host, clap_plugin_timer_support, ExtensionType = E>,
+    E: PluginTimerSupportPrototype<'host, Parent = P, Base = clap_plugin_timer_support>,
+{
+    guard::release_call::<P, _, _>(plugin, "clap_plugin_timer_support.unregister_all", || {
+        let main_thread =
+            thread::main_thread::<P>(plugin, "clap_plugin_timer_support.unregister_all");
+        let Some(timers) = get_ext::<P, E>(plugin).and_then(|p| p.timers()) else {
+            return;
+        };
+        let Some(instance) = PluginInstance::<P>::from_plugin(plugin) else {
+            return;
+        };
+        timers.unregister_all(instance.host(), main_thread)
+    })
+}
+
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_timer_support
+where
+    P: HasExtension<'host, clap_plugin_timer_support, ExtensionType = E>,
+    E: PluginTimerSupportPrototype<'host, Parent = P, Base = clap_plugin_timer_support>,
+{
+    &clap_plugin_timer_support {
+        on_timer: Some(on_timer::<'host, P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_timer_support, ExtensionType = E>,
+    E: PluginTimerSupportPrototype<'host, Parent = P, Base = clap_plugin_timer_support>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AbstractPrototype;
+    use crate::testing::{StubHost, TestPlugin};
+
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn register_and_unregister() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let registry = &test_plugin.timers.registry;
+        let main_thread = unsafe { MainThread::new_unchecked() };
+        let ticks = Arc::new(AtomicU32::new(0));
+        let counter = ticks.clone();
+        let ticking = registry
+            .register(test_plugin, main_thread, 10, move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+            })
+            .unwrap();
+        let idle = registry
+            .register(test_plugin, main_thread, 20, |_| {})
+            .unwrap();
+        assert_eq!(*host.registered_timers.lock().unwrap(), [ticking, idle]);
+
+        let on_timer = test_plugin.timers.as_base().on_timer.unwrap();
+        unsafe {
+            on_timer(plugin, ticking);
+            on_timer(plugin, ticking);
+            // Not in the registry, left to the plugin
+            on_timer(plugin, idle + 1);
+        }
+        assert_eq!(ticks.load(Ordering::Relaxed), 2);
+        assert_eq!(test_plugin.timers.ticks.load(Ordering::Relaxed), 1);
+
+        assert!(registry.unregister(test_plugin, main_thread, ticking));
+        assert!(!registry.unregister(test_plugin, main_thread, ticking));
+        assert!(!registry.contains(ticking));
+        assert_eq!(*host.unregistered_timers.lock().unwrap(), [ticking]);
+        unsafe { on_timer(plugin, ticking) };
+        assert_eq!(ticks.load(Ordering::Relaxed), 2);
+
+        // The timers left are unregistered along with the plugin
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+        assert_eq!(*host.unregistered_timers.lock().unwrap(), [ticking, idle]);
+    }
+}
@@ -0,0 +1,205 @@
+//! Voice info
+//!
+//! Reports how many voices a polyphonic plugin has, so that the host can tell
+//! how to route per-note modulation.
+//!
+//! Whenever the voice count or capacity change, like when the polyphony parameter
+//! is set, the host is to be told through [`voice_info_changed`].
+
+use core::ffi::CStr;
+
+use crate::ext::{ExtensionPrototype, PluginExtensionBase, ProtoPtr, clap_flags};
+use crate::guard;
+use crate::host::ext::voice_info::HostVoiceInfo;
+use crate::log::Severity;
+use crate::plugin::HasExtension;
+use crate::plugin::instance::PluginInstance;
+use crate::thread::{self, MainThread};
+
+use clap_sys::ext::voice_info::{
+    CLAP_EXT_VOICE_INFO, CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES, clap_plugin_voice_info,
+    clap_voice_info,
+};
+use clap_sys::plugin::clap_plugin;
+
+clap_flags! {
+    /// The capabilities of the voices of a plugin
+    VoiceInfoFlags(u64) {
+        /// Several notes with the same key and channel may play at once,
+        /// so they can only be told apart by their note id
+        SUPPORTS_OVERLAPPING_NOTES = CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES;
+    }
+}
+
+/// Describes the voices of a plugin, see `clap_voice_info`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VoiceInfo {
+    /// The number of voices the plugin plays with, like its polyphony setting
+    pub voice_count: u32,
+    /// The number of voices the plugin can allocate, at least `voice_count`,
+    /// the info is refused otherwise
+    pub voice_capacity: u32,
+    pub flags: VoiceInfoFlags,
+}
+impl VoiceInfo {
+    pub const fn to_raw(&self) -> clap_voice_info {
+        clap_voice_info {
+            voice_count: self.voice_count,
+            voice_capacity: self.voice_capacity,
+            flags: self.flags.bits(),
+        }
+    }
+}
+
+pub trait PluginVoiceInfoPrototype<'host>:
+    ExtensionPrototype<'host, Base = clap_plugin_voice_info>
+{
+    /// `main-thread` & `active`
+    ///
+    /// Info about the voices of the plugin.
+    fn get(&self, main_thread: MainThread) -> Option<VoiceInfo>;
+}
+
+impl PluginExtensionBase for clap_plugin_voice_info {
+    const ID: &'static CStr = CLAP_EXT_VOICE_INFO;
+}
+
+/// `main-thread`
+///
+/// Tells the host that the voice info of `plugin` changed, so that it queries it again.
+///
+/// A change of the polyphony parameter reaches the plugin through
+/// [`PluginParamsPrototype::flush`](crate::ext::params::PluginParamsPrototype::flush) or
+/// [`PluginPrototype::process`](crate::plugin::PluginPrototype::process), usually on the
+/// audio thread. The plugin then asks for [`Host::request_callback`](crate::host::Host::request_callback),
+/// and calls this from [`PluginPrototype::on_main_thread`](crate::plugin::PluginPrototype::on_main_thread).
+///
+/// Returns false if the host could not be told, either because `plugin` was not
+/// created by a factory, or the host lacks `clap_host_voice_info`.
+pub fn voice_info_changed<'host, P>(plugin: &P, main_thread: MainThread) -> bool
+where
+    P: HasExtension<'host, clap_plugin_voice_info>,
+{
+    let Some(instance) = PluginInstance::of(plugin) else {
+        return false;
+    };
+    let Some(host_voice_info) = instance.host().get_extension::<HostVoiceInfo>() else {
+        return false;
+    };
+    host_voice_info.changed(main_thread);
+    true
+}
+
+fn get_ext<'host, 'ext, P, E>(ptr: *const clap_plugin) -> Option<&'ext E>
+where
+    P: HasExtension<'host, clap_plugin_voice_info, ExtensionType = E> + 'ext,
+    E: PluginVoiceInfoPrototype<'host>,
+    'host: 'ext,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    let parent = unsafe { (plugin.plugin_data as *const P).as_ref() }?;
+    Some(parent.get_extension())
+}
+#[inline(never)]
+unsafe extern "C" fn get<'host, P, E>(
+    plugin: *const clap_plugin,
+    info_dst: *mut clap_voice_info,
+) -> bool
+where
+    P: HasExtension<'host, clap_plugin_voice_info, ExtensionType = E>,
+    E: PluginVoiceInfoPrototype<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin_voice_info.get", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin_voice_info.get");
+        let Some(ext) = get_ext::<P, E>(plugin) else {
+            return false;
+        };
+        let Some(info) = ext.get(main_thread) else {
+            return false;
+        };
+        if info.voice_count > info.voice_capacity {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::PluginMisbehaving,
+                format_args!(
+                    "voice count {} exceeds the voice capacity {}",
+                    info.voice_count, info.voice_capacity
+                ),
+            );
+            return false;
+        }
+        let Some(info_dst) = (unsafe { info_dst.as_mut() }) else {
+            PluginInstance::<P>::logger_of(plugin).log(
+                Severity::HostMisbehaving,
+                format_args!("clap_plugin_voice_info.get: null info"),
+            );
+            return false;
+        };
+        *info_dst = info.to_raw();
+        true
+    })
+}
+pub const fn vtable<'host, P, E>() -> &'static clap_plugin_voice_info
+where
+    P: HasExtension<'host, clap_plugin_voice_info, ExtensionType = E>,
+    E: PluginVoiceInfoPrototype<'host>,
+{
+    &clap_plugin_voice_info {
+        get: Some(get::<P, E>),
+    }
+}
+pub const fn extension_pointer<'host, P, E>() -> ProtoPtr<'host, E>
+where
+    P: HasExtension<'host, clap_plugin_voice_info, ExtensionType = E>,
+    E: PluginVoiceInfoPrototype<'host, Parent = P>,
+{
+    let vt = vtable::<P, E>() as *const _;
+    ProtoPtr(vt, ::core::marker::PhantomData)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{StubHost, TestPlugin, TestVoices};
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn flags() {
+        assert_eq!(
+            VoiceInfoFlags::from_bits_truncate(u64::MAX),
+            VoiceInfoFlags::SUPPORTS_OVERLAPPING_NOTES
+        );
+        assert_eq!(
+            TestVoices::INFO.to_raw().flags,
+            CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES
+        );
+    }
+
+    #[test]
+    fn get_and_changed() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let main_thread = unsafe { MainThread::new_unchecked() };
+        let voice_info = unsafe {
+            &*((*plugin).get_extension.unwrap()(plugin, CLAP_EXT_VOICE_INFO.as_ptr())
+                as *const clap_plugin_voice_info)
+        };
+        let mut info = TestVoices::INFO.to_raw();
+        info.voice_count = 0;
+        assert!(unsafe { voice_info.get.unwrap()(plugin, &mut info) });
+        assert_eq!(info.voice_count, TestVoices::INFO.voice_count);
+        assert!(!unsafe { voice_info.get.unwrap()(plugin, ::core::ptr::null_mut()) });
+
+        *test_plugin.voices.info.lock().unwrap() = VoiceInfo {
+            voice_count: 32,
+            ..TestVoices::INFO
+        };
+        info.voice_count = 0;
+        assert!(!unsafe { voice_info.get.unwrap()(plugin, &mut info) });
+        assert_eq!(info.voice_count, 0);
+
+        assert!(voice_info_changed(test_plugin, main_thread));
+        assert_eq!(host.voice_info_changes.load(Ordering::Relaxed), 1);
+        unsafe { (*plugin).destroy.unwrap()(plugin) };
+    }
+}
@@ -1,18 +1,101 @@
-use crate::plugin::{PluginName, PluginPrototype};
+//! Factory
+//!
+//! A [`PluginFactoryPrototype`] creates a single type of plugin, a bundle of
+//! several types shares one factory through a [`PluginBundle`], which routes
+//! `create_plugin` by the id of each descriptor.
+//!
+//! Like plugins and their `clap_plugin`, a factory is handed to the host as the
+//! address of its vtable, which must be the first field of the `#[repr(C)]`
+//! factory, see [`vtable`]. The [`entrypoint!`](crate::entrypoint) hands out any
+//! [`AbstractPrototype`] whose base is a [`FactoryBase`].
+
+use crate::AbstractPrototype;
+use crate::guard;
+use crate::host::Host;
+use crate::log::{self, Severity};
+use crate::plugin::instance::PluginInstance;
+use crate::plugin::{PluginID, PluginPrototype};
 use clap_sys::{
-    factory::plugin_factory::clap_plugin_factory,
+    factory::{
+        draft::{
+            plugin_invalidation::{
+                CLAP_PLUGIN_INVALIDATION_FACTORY_ID, clap_plugin_invalidation_factory,
+            },
+            plugin_state_converter::{
+                CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID, clap_plugin_state_converter_factory,
+            },
+        },
+        plugin_factory::{CLAP_PLUGIN_FACTORY_ID, clap_plugin_factory},
+        preset_discovery::{
+            CLAP_PRESET_DISCOVERY_FACTORY_ID, CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT,
+            clap_preset_discovery_factory,
+        },
+    },
     host::clap_host,
     plugin::{clap_plugin, clap_plugin_descriptor},
 };
-use core::ffi::c_char;
-pub trait PluginFactoryPrototype<'host> {
+use core::ffi::{CStr, c_char, c_void};
+
+pub mod bundle;
+
+pub use bundle::{BundleEntry, BundledPlugin, PluginBundle};
+
+::clap_proc_tools::ez_c_str! { FactoryID }
+
+/// Implemented by the vtable type of every factory,
+/// ties it to the identifiers the host may query it by.
+pub trait FactoryBase {
+    const ID: &'static CStr;
+    /// Older draft or compatibility identifiers, which should resolve to the
+    /// same factory as [`FactoryBase::ID`].
+    const COMPAT_IDS: &'static [&'static CStr] = &[];
+    #[inline]
+    fn matches(id: &FactoryID) -> bool {
+        id == Self::ID || Self::COMPAT_IDS.iter().any(|compat_id| id == *compat_id)
+    }
+}
+impl FactoryBase for clap_plugin_factory {
+    const ID: &'static CStr = CLAP_PLUGIN_FACTORY_ID;
+}
+impl FactoryBase for clap_preset_discovery_factory {
+    const ID: &'static CStr = CLAP_PRESET_DISCOVERY_FACTORY_ID;
+    const COMPAT_IDS: &'static [&'static CStr] = &[CLAP_PRESET_DISCOVERY_FACTORY_ID_COMPAT];
+}
+impl FactoryBase for clap_plugin_invalidation_factory {
+    const ID: &'static CStr = CLAP_PLUGIN_INVALIDATION_FACTORY_ID;
+}
+impl FactoryBase for clap_plugin_state_converter_factory {
+    const ID: &'static CStr = CLAP_PLUGIN_STATE_CONVERTER_FACTORY_ID;
+}
+
+/// The pointer the entrypoint hands to the host for `factory`, null if there is none.
+///
+/// Used by the code generated by [`entrypoint!`](crate::entrypoint), which checks
+/// every factory against the base of the id it is registered under through `B`.
+pub fn hand_out<'host, F, B>(factory: Option<&'host F>) -> *const c_void
+where
+    F: AbstractPrototype<'host, Base = B>,
+    B: FactoryBase,
+{
+    factory.map_or(::core::ptr::null(), |factory| {
+        factory.as_base() as *const B as *const c_void
+    })
+}
+
+/// A factory of a single type of plugin
+///
+/// The factory must be `#[repr(C)]`, with the `clap_plugin_factory` returned by
+/// [`vtable`] as its first field.
+pub trait PluginFactoryPrototype<'host>:
+    AbstractPrototype<'host, Base = clap_plugin_factory>
+{
     type Produced: PluginPrototype<'host, Base = clap_plugin>;
     fn get_plugin_count(&self) -> u32;
     fn get_plugin_descriptor(&self, plugin_idx: u32) -> Option<&'static clap_plugin_descriptor>;
     fn create_plugin<'id>(
         &self,
-        host: &'host clap_host,
-        plugin_id: &'id PluginName,
+        host: Host<'host>,
+        plugin_id: &'id PluginID,
     ) -> Option<Self::Produced>;
 }
 
@@ -20,11 +103,16 @@ unsafe extern "C" fn get_plugin_count<'host, F>(factory_ptr: *const clap_plugin_
 where
     F: PluginFactoryPrototype<'host>,
 {
-    let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
-        println!("NULL FACTORY");
-        return 0;
-    };
-    factory.get_plugin_count()
+    guard::call("clap_plugin_factory.get_plugin_count", || {
+        let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("null plugin factory"),
+            );
+            return 0;
+        };
+        factory.get_plugin_count()
+    })
 }
 unsafe extern "C" fn get_plugin_descriptor<'host, F>(
     factory_ptr: *const clap_plugin_factory,
@@ -33,15 +121,23 @@ unsafe extern "C" fn get_plugin_descriptor<'host, F>(
 where
     F: PluginFactoryPrototype<'host>,
 {
-    let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
-        println!("NULL FACTORY");
-        return ::core::ptr::null();
-    };
-    let Some(descriptor) = factory.get_plugin_descriptor(plugin_idx) else {
-        println!("NULL DESCRIPTOR");
-        return ::core::ptr::null();
-    };
-    descriptor as *const _ as *const _
+    guard::call("clap_plugin_factory.get_plugin_descriptor", || {
+        let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("null plugin factory"),
+            );
+            return ::core::ptr::null();
+        };
+        let Some(descriptor) = factory.get_plugin_descriptor(plugin_idx) else {
+            log::log(
+                Severity::Debug,
+                format_args!("no plugin descriptor at index {plugin_idx}"),
+            );
+            return ::core::ptr::null();
+        };
+        descriptor as *const _ as *const _
+    })
 }
 unsafe extern "C" fn create_plugin<'host, F>(
     factory_ptr: *const clap_plugin_factory,
@@ -52,37 +148,77 @@ where
     F: PluginFactoryPrototype<'host>,
     F::Produced: PluginPrototype<'host, Base = clap_plugin>,
 {
-    println!("CREATE PLUGIN");
-    let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
-        println!("NULL FACTORY");
-        return ::core::ptr::null();
-    };
-    let Some(host) = (unsafe { host_ptr.as_ref() }) else {
-        println!("NULL HOST");
-        return ::core::ptr::null();
+    guard::call("clap_plugin_factory.create_plugin", || {
+        let Some(factory) = (unsafe { (factory_ptr as *const F).as_ref() }) else {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("null plugin factory"),
+            );
+            return ::core::ptr::null();
+        };
+        // SAFETY: The host outlives the plugins it creates
+        let Some((host, plugin_id)) = (unsafe { create_plugin_args(host_ptr, plugin_id) }) else {
+            return ::core::ptr::null();
+        };
+        let Some(plugin_interface) = factory.create_plugin(host, plugin_id) else {
+            log::log(
+                Severity::Warning,
+                format_args!("factory created no plugin for {plugin_id:?}"),
+            );
+            return ::core::ptr::null();
+        };
+        instantiate(plugin_interface, host)
+    })
+}
+/// Checks the arguments of `clap_plugin_factory.create_plugin` common to every factory.
+///
+/// Returns [`None`], after logging why, if the host is null or the id is invalid.
+///
+/// # Safety
+///
+/// `host_ptr` must be null, or point to a host outliving the plugins it creates.
+pub(crate) unsafe fn create_plugin_args<'id>(
+    host_ptr: *const clap_host,
+    plugin_id: *const c_char,
+) -> Option<(Host<'static>, &'id PluginID)> {
+    let Some(host) = (unsafe { host_ptr.as_ref::<'static>() }) else {
+        log::log(Severity::HostMisbehaving, format_args!("null host"));
+        return None;
     };
-    let plugin_id = match plugin_id.try_into() {
-        Ok(val) => val,
+    let host = unsafe { Host::from_raw(host) };
+    match plugin_id.try_into() {
+        Ok(plugin_id) => Some((host, plugin_id)),
         Err(e) => {
-            println!("ERR {e:?}");
-            return ::core::ptr::null();
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("invalid plugin id: {e:?}"),
+            );
+            None
         }
-    };
-    // Some really hairy shit coming up
-    let Some(plugin_interface) = factory.create_plugin(host, plugin_id) else {
-        println!("NO PLUGIN");
-        return ::core::ptr::null();
-    };
-    // Leak the MF, let's hope
-    let plugin = Box::leak(Box::new(plugin_interface));
-    let addr = plugin as *mut _ as *mut ::core::ffi::c_void;
-    let vt = unsafe { (plugin as *mut _ as *mut clap_plugin).as_mut() };
-    if vt.is_none() {
-        println!("VT SHIT");
+    }
+}
+/// Hands a freshly created plugin out to the host.
+///
+/// The plugin is leaked until the host calls `clap_plugin.destroy`, returns null
+/// if its `clap_plugin` is misplaced.
+pub(crate) fn instantiate<'host, P>(plugin: P, host: Host<'static>) -> *const clap_plugin
+where
+    P: PluginPrototype<'host, Base = clap_plugin>,
+{
+    let instance = PluginInstance::leak(plugin, host);
+    let addr = instance as *mut PluginInstance<P>;
+    // The host is handed the plugin's own address, so the `clap_plugin`
+    // built by `plugin::vtable` has to be the very first thing in it.
+    if !::core::ptr::eq(instance.plugin.as_base(), addr as *const clap_plugin) {
+        log::log(
+            Severity::PluginMisbehaving,
+            format_args!("the clap_plugin is not at the start of the plugin"),
+        );
+        drop(PluginInstance::reclaim_raw(addr));
         return ::core::ptr::null();
     }
-    let vt = unsafe { vt.unwrap_unchecked() };
-    vt.plugin_data = addr;
+    let vt = unsafe { &mut *(addr as *mut clap_plugin) };
+    vt.plugin_data = addr as *mut ::core::ffi::c_void;
     vt
 }
 pub const fn vtable<'host, F>() -> &'static clap_plugin_factory
@@ -0,0 +1,322 @@
+//! Bundle
+//!
+//! A [`PluginBundle`] is a factory for several types of plugins, each registered
+//! along with its descriptor as a [`BundleEntry`]. The host's requested id is
+//! compared against the [`PluginID`] of every descriptor, the first match
+//! creates the plugin.
+//!
+//! ```
+//! # use clap_prototype::{AbstractPrototype, ProcessResult};
+//! # use clap_prototype::ext::ExtensionRegistry;
+//! # use clap_prototype::factory::{BundleEntry, BundledPlugin, PluginBundle};
+//! # use clap_prototype::host::Host;
+//! # use clap_prototype::plugin::{self, PluginPrototype, plugin_descriptor};
+//! # use clap_prototype::process::ProcessContext;
+//! # use clap_prototype::thread::{AudioThread, MainThread};
+//! # use clap_sys::plugin::{clap_plugin, clap_plugin_descriptor};
+//! # macro_rules! plugin {
+//! #     ($name:ident) => {
+//! #         #[derive(ExtensionRegistry)]
+//! #         #[repr(C)]
+//! #         struct $name {
+//! #             base: clap_plugin,
+//! #         }
+//! #         impl<'host> AbstractPrototype<'host> for $name {
+//! #             type Base = clap_plugin;
+//! #             fn as_base(&self) -> &clap_plugin {
+//! #                 &self.base
+//! #             }
+//! #         }
+//! #         impl<'host> PluginPrototype<'host> for $name {
+//! #             fn init(&self, _: MainThread) -> bool { true }
+//! #             fn activate(&self, _: MainThread, _: f64, _: u32, _: u32) -> bool { true }
+//! #             fn deactivate(&self, _: MainThread) {}
+//! #             fn start_processing(&self, _: AudioThread) -> bool { true }
+//! #             fn stop_processing(&self, _: AudioThread) {}
+//! #             fn reset(&self, _: AudioThread) {}
+//! #             fn process(&self, _: AudioThread, _: &mut ProcessContext<'_>) -> ProcessResult {
+//! #                 ProcessResult::Continue
+//! #             }
+//! #         }
+//! #         impl<'host> BundledPlugin<'host> for $name {
+//! #             fn create(_: Host<'host>, descriptor: &'static clap_plugin_descriptor) -> Option<Self> {
+//! #                 Some($name { base: plugin::vtable::<$name>(descriptor) })
+//! #             }
+//! #         }
+//! #     };
+//! # }
+//! # plugin!(Synth);
+//! # plugin!(Reverb);
+//! static FACTORY: PluginBundle = PluginBundle::new(&[
+//!     BundleEntry::new::<Synth>(plugin_descriptor! {
+//!         id: "com.example.synth",
+//!         name: "Synth",
+//!         version: "1.0.0",
+//!         features: [Instrument, Synthesizer],
+//!     }),
+//!     BundleEntry::new::<Reverb>(plugin_descriptor! {
+//!         id: "com.example.reverb",
+//!         name: "Reverb",
+//!         version: "1.0.0",
+//!         features: [AudioEffect, Reverb],
+//!     }),
+//! ]);
+//! # assert_eq!(FACTORY.entries().len(), 2);
+//! ```
+
+use core::ffi::c_char;
+
+use clap_sys::{
+    factory::plugin_factory::clap_plugin_factory,
+    host::clap_host,
+    plugin::{clap_plugin, clap_plugin_descriptor},
+};
+
+use crate::AbstractPrototype;
+use crate::factory::{create_plugin_args, instantiate};
+use crate::guard;
+use crate::host::Host;
+use crate::log::{self, Severity};
+use crate::plugin::{PluginID, PluginPrototype};
+
+/// A plugin type which can be registered in a [`PluginBundle`]
+pub trait BundledPlugin<'host>: PluginPrototype<'host, Base = clap_plugin> + Sized {
+    /// `main-thread`
+    ///
+    /// Creates the plugin, `descriptor` is the one it was registered with.
+    fn create(host: Host<'host>, descriptor: &'static clap_plugin_descriptor) -> Option<Self>;
+}
+
+/// A plugin type of a [`PluginBundle`], and its descriptor
+pub struct BundleEntry<'host> {
+    descriptor: &'static clap_plugin_descriptor,
+    create: fn(Host<'static>, &'static clap_plugin_descriptor) -> *const clap_plugin,
+    _host: ::core::marker::PhantomData<fn(Host<'host>)>,
+}
+impl<'host> BundleEntry<'host> {
+    pub const fn new<P>(descriptor: &'static clap_plugin_descriptor) -> BundleEntry<'host>
+    where
+        P: BundledPlugin<'host>,
+    {
+        BundleEntry {
+            descriptor,
+            create: create_entry::<'host, P>,
+            _host: ::core::marker::PhantomData,
+        }
+    }
+    #[inline]
+    pub const fn descriptor(&self) -> &'static clap_plugin_descriptor {
+        self.descriptor
+    }
+    /// The id of the descriptor, [`None`] if it is null
+    pub fn id(&self) -> Option<&'static PluginID> {
+        if self.descriptor.id.is_null() {
+            return None;
+        }
+        Some(unsafe { PluginID::from_ptr(self.descriptor.id) })
+    }
+}
+fn create_entry<'host, P>(
+    host: Host<'static>,
+    descriptor: &'static clap_plugin_descriptor,
+) -> *const clap_plugin
+where
+    P: BundledPlugin<'host>,
+{
+    match P::create(host, descriptor) {
+        Some(plugin) => instantiate(plugin, host),
+        None => ::core::ptr::null(),
+    }
+}
+
+/// A `clap_plugin_factory` over several types of plugins
+#[repr(C)]
+pub struct PluginBundle<'host> {
+    base: clap_plugin_factory,
+    entries: &'host [BundleEntry<'host>],
+}
+impl<'host> AbstractPrototype<'host> for PluginBundle<'host> {
+    type Base = clap_plugin_factory;
+    #[inline]
+    fn as_base(&self) -> &clap_plugin_factory {
+        &self.base
+    }
+}
+impl<'host> PluginBundle<'host> {
+    pub const fn new(entries: &'host [BundleEntry<'host>]) -> PluginBundle<'host> {
+        PluginBundle {
+            base: clap_plugin_factory {
+                get_plugin_count: Some(get_plugin_count),
+                get_plugin_descriptor: Some(get_plugin_descriptor),
+                create_plugin: Some(create_plugin),
+            },
+            entries,
+        }
+    }
+    #[inline]
+    pub const fn entries(&self) -> &'host [BundleEntry<'host>] {
+        self.entries
+    }
+    /// The entry whose descriptor has the id `plugin_id`
+    pub fn find(&self, plugin_id: &PluginID) -> Option<&'host BundleEntry<'host>> {
+        self.entries
+            .iter()
+            .find(|entry| entry.id().is_some_and(|id| id == plugin_id.as_ref()))
+    }
+    /// # Safety
+    ///
+    /// `factory_ptr` must be null, or the `base` of a [`PluginBundle`].
+    unsafe fn from_raw<'a>(factory_ptr: *const clap_plugin_factory) -> Option<&'a Self> {
+        let bundle = unsafe { (factory_ptr as *const PluginBundle<'host>).as_ref() };
+        if bundle.is_none() {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("null plugin factory"),
+            );
+        }
+        bundle
+    }
+}
+
+unsafe extern "C" fn get_plugin_count(factory_ptr: *const clap_plugin_factory) -> u32 {
+    guard::call("clap_plugin_factory.get_plugin_count", || {
+        let Some(bundle) = (unsafe { PluginBundle::from_raw(factory_ptr) }) else {
+            return 0;
+        };
+        bundle.entries.len() as u32
+    })
+}
+unsafe extern "C" fn get_plugin_descriptor(
+    factory_ptr: *const clap_plugin_factory,
+    plugin_idx: u32,
+) -> *const clap_plugin_descriptor {
+    guard::call("clap_plugin_factory.get_plugin_descriptor", || {
+        let Some(bundle) = (unsafe { PluginBundle::from_raw(factory_ptr) }) else {
+            return ::core::ptr::null();
+        };
+        match bundle.entries.get(plugin_idx as usize) {
+            Some(entry) => entry.descriptor,
+            None => {
+                log::log(
+                    Severity::Debug,
+                    format_args!("no plugin descriptor at index {plugin_idx}"),
+                );
+                ::core::ptr::null()
+            }
+        }
+    })
+}
+unsafe extern "C" fn create_plugin(
+    factory_ptr: *const clap_plugin_factory,
+    host_ptr: *const clap_host,
+    plugin_id: *const c_char,
+) -> *const clap_plugin {
+    guard::call("clap_plugin_factory.create_plugin", || {
+        let Some(bundle) = (unsafe { PluginBundle::from_raw(factory_ptr) }) else {
+            return ::core::ptr::null();
+        };
+        // SAFETY: The host outlives the plugins it creates
+        let Some((host, plugin_id)) = (unsafe { create_plugin_args(host_ptr, plugin_id) }) else {
+            return ::core::ptr::null();
+        };
+        let Some(entry) = bundle.find(plugin_id) else {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("no plugin with the id {plugin_id:?} in the bundle"),
+            );
+            return ::core::ptr::null();
+        };
+        let plugin = (entry.create)(host, entry.descriptor);
+        if plugin.is_null() {
+            log::log(
+                Severity::Warning,
+                format_args!("bundle created no plugin for {plugin_id:?}"),
+            );
+        }
+        plugin
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::plugin::PluginExtensionID;
+    use crate::thread::{AudioThread, MainThread};
+
+    #[repr(C)]
+    struct Probe {
+        base: clap_plugin,
+    }
+    impl<'host> AbstractPrototype<'host> for Probe {
+        type Base = clap_plugin;
+        fn as_base(&self) -> &clap_plugin {
+            &self.base
+        }
+    }
+    impl<'host> crate::ext::ExtensionRegistry<'host> for Probe {
+        fn lookup(&self, _id: &PluginExtensionID) -> Option<*const ::core::ffi::c_void> {
+            None
+        }
+    }
+    impl<'host> PluginPrototype<'host> for Probe {
+        fn init(&self, _main_thread: MainThread) -> bool {
+            true
+        }
+        fn activate(&self, _main_thread: MainThread, _: f64, _: u32, _: u32) -> bool {
+            true
+        }
+        fn deactivate(&self, _main_thread: MainThread) {}
+        fn start_processing(&self, _audio_thread: AudioThread) -> bool {
+            true
+        }
+        fn stop_processing(&self, _audio_thread: AudioThread) {}
+        fn reset(&self, _audio_thread: AudioThread) {}
+        fn process(
+            &self,
+            _audio_thread: AudioThread,
+            _process: &mut crate::process::ProcessContext<'_>,
+        ) -> crate::ProcessResult {
+            crate::ProcessResult::Continue
+        }
+    }
+    impl<'host> BundledPlugin<'host> for Probe {
+        fn create(_host: Host<'host>, descriptor: &'static clap_plugin_descriptor) -> Option<Self> {
+            Some(Probe {
+                base: crate::plugin::vtable::<Probe>(descriptor),
+            })
+        }
+    }
+
+    fn descriptor(id: &'static ::core::ffi::CStr) -> clap_plugin_descriptor {
+        let mut descriptor: clap_plugin_descriptor = unsafe { ::core::mem::zeroed() };
+        descriptor.id = id.as_ptr();
+        descriptor
+    }
+
+    #[test]
+    fn dispatches_by_id() {
+        let first = Box::leak(Box::new(descriptor(c"com.example.first")));
+        let second = Box::leak(Box::new(descriptor(c"com.example.second")));
+        let entries = Box::leak(Box::new([
+            BundleEntry::new::<Probe>(first),
+            BundleEntry::new::<Probe>(second),
+        ]));
+        let bundle = PluginBundle::new(entries);
+        let factory = bundle.as_base();
+        let host: clap_host = unsafe { ::core::mem::zeroed() };
+        unsafe {
+            assert_eq!(factory.get_plugin_count.unwrap()(factory), 2);
+            assert!(::core::ptr::eq(
+                factory.get_plugin_descriptor.unwrap()(factory, 1),
+                second
+            ));
+            assert!(factory.get_plugin_descriptor.unwrap()(factory, 2).is_null());
+            let create = factory.create_plugin.unwrap();
+            assert!(create(factory, &host, c"com.example.third".as_ptr()).is_null());
+            let plugin = create(factory, &host, c"com.example.second".as_ptr());
+            assert!(::core::ptr::eq((*plugin).desc, second));
+            (*plugin).destroy.unwrap()(plugin);
+        }
+    }
+}
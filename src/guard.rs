@@ -0,0 +1,205 @@
+//! Guard
+//!
+//! Unwinding out of an `extern "C"` function aborts the process, taking the host
+//! down along with the plugin. Every thunk handed to the host runs its body
+//! through [`call`], which catches a panic and returns the failure value of the
+//! callback instead, as if the plugin had simply refused the call.
+//!
+//! The message of the latest panic is logged, and kept around, see [`last_panic`].
+//!
+//! A plugin setting [`PluginPrototype::POISON_ON_PANIC`] is in addition marked
+//! as poisoned once it panicked, every later callback to it then returns the
+//! failure value without reaching the plugin, as its state can no longer be
+//! trusted. Only `clap_plugin.destroy` still goes through, along with whatever
+//! releases resources held from the host, like the GUI or the timers, see [`release_call`].
+
+use std::any::Any;
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::sync::Mutex;
+
+use clap_sys::plugin::clap_plugin;
+
+use crate::log::{Logger, Severity};
+use crate::plugin::PluginPrototype;
+use crate::plugin::instance::PluginInstance;
+
+/// What a callback returns to the host when it could not complete
+pub trait FailureValue {
+    fn failure() -> Self;
+}
+impl FailureValue for () {
+    #[inline]
+    fn failure() -> Self {}
+}
+impl FailureValue for bool {
+    #[inline]
+    fn failure() -> Self {
+        false
+    }
+}
+impl FailureValue for u32 {
+    #[inline]
+    fn failure() -> Self {
+        0
+    }
+}
+/// `CLAP_PROCESS_ERROR`, for `clap_plugin.process`
+impl FailureValue for i32 {
+    #[inline]
+    fn failure() -> Self {
+        clap_sys::process::CLAP_PROCESS_ERROR
+    }
+}
+impl<T> FailureValue for *const T {
+    #[inline]
+    fn failure() -> Self {
+        ::core::ptr::null()
+    }
+}
+impl<T> FailureValue for *mut T {
+    #[inline]
+    fn failure() -> Self {
+        ::core::ptr::null_mut()
+    }
+}
+
+static LAST_PANIC: Mutex<Option<String>> = Mutex::new(None);
+
+/// The callback which last panicked, and its panic message
+pub fn last_panic() -> Option<String> {
+    LAST_PANIC
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .clone()
+}
+/// Like [`last_panic`], forgetting the panic.
+pub fn take_last_panic() -> Option<String> {
+    LAST_PANIC
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take()
+}
+fn record(callback: &'static str, payload: Box<dyn Any + Send>, logger: Logger<'_>) {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.as_str()
+    } else {
+        "Box<dyn Any>"
+    };
+    logger.log(
+        Severity::PluginMisbehaving,
+        format_args!("{callback} panicked: {message}"),
+    );
+    *LAST_PANIC
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(format!("{callback}: {message}"));
+}
+
+/// Runs the body of the callback `callback`, returning its failure value if it panicked.
+pub fn call<R, F>(callback: &'static str, f: F) -> R
+where
+    R: FailureValue,
+    F: FnOnce() -> R,
+{
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            record(callback, payload, Logger::fallback());
+            R::failure()
+        }
+    }
+}
+/// Like [`call`], for a callback on a plugin instance, short-circuiting once poisoned.
+pub(crate) fn plugin_call<'host, P, R, F>(
+    plugin: *const clap_plugin,
+    callback: &'static str,
+    f: F,
+) -> R
+where
+    P: PluginPrototype<'host>,
+    R: FailureValue,
+    F: FnOnce() -> R,
+{
+    let instance = PluginInstance::<P>::from_plugin(plugin);
+    if instance.is_some_and(PluginInstance::is_poisoned) {
+        return R::failure();
+    }
+    instance_call::<P, R, F>(instance, callback, f)
+}
+/// Like [`plugin_call`], but also reaching a poisoned plugin, for the callbacks
+/// releasing what the plugin holds from the host, which must not outlive it.
+pub(crate) fn release_call<'host, P, R, F>(
+    plugin: *const clap_plugin,
+    callback: &'static str,
+    f: F,
+) -> R
+where
+    P: PluginPrototype<'host>,
+    R: FailureValue,
+    F: FnOnce() -> R,
+{
+    instance_call::<P, R, F>(PluginInstance::<P>::from_plugin(plugin), callback, f)
+}
+fn instance_call<'host, P, R, F>(
+    instance: Option<&PluginInstance<P>>,
+    callback: &'static str,
+    f: F,
+) -> R
+where
+    P: PluginPrototype<'host>,
+    R: FailureValue,
+    F: FnOnce() -> R,
+{
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => value,
+        Err(payload) => {
+            record(
+                callback,
+                payload,
+                instance.map_or(Logger::fallback(), PluginInstance::logger),
+            );
+            if let Some(instance) = instance.filter(|_| P::POISON_ON_PANIC) {
+                instance.poison();
+            }
+            R::failure()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{StubHost, TestPlugin};
+    use crate::thread::MainThread;
+
+    #[test]
+    fn catches_panics() {
+        assert!(call("test.ok", || true));
+        let failed: bool = call("test.panic", || panic!("boom {}", 1));
+        assert!(!failed);
+        assert!(call::<*const u8, _>("test.null", || panic!()).is_null());
+        let last_panic = take_last_panic();
+        assert!(last_panic.is_some_and(|message| message.starts_with("test.null")));
+    }
+    #[test]
+    fn releases_poisoned_plugins() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        let test_plugin = TestPlugin::of(plugin);
+        let main_thread = unsafe { MainThread::new_unchecked() };
+        let timer_id = test_plugin
+            .timers
+            .registry
+            .register(test_plugin, main_thread, 10, |_| {})
+            .unwrap();
+        PluginInstance::<TestPlugin>::from_plugin(plugin)
+            .unwrap()
+            .poison();
+        unsafe {
+            assert!(!(*plugin).init.unwrap()(plugin));
+            (*plugin).destroy.unwrap()(plugin);
+        }
+        assert_eq!(*host.unregistered_timers.lock().unwrap(), [timer_id]);
+    }
+}
@@ -0,0 +1,190 @@
+//! Host
+//!
+//! Safe view over the `clap_host` handed to
+//! [`PluginFactoryPrototype::create_plugin`](crate::factory::PluginFactoryPrototype::create_plugin).
+//!
+//! Host extensions mirror the plugin-side ones: the raw vtable implements
+//! [`HostExtensionBase`], tying it to its identifier, and a wrapper implementing
+//! [`HostExtension`] is built from it by [`Host::get_extension`].
+
+pub mod ext;
+pub(crate) mod str_types;
+
+use core::ffi::{CStr, c_void};
+
+use clap_sys::host::clap_host;
+use clap_sys::version::clap_version;
+
+pub use str_types::*;
+
+use crate::AbstractPrototype;
+use crate::log::Logger;
+
+/// Implemented by the vtable type of every host-side extension,
+/// ties it to the identifiers it is queried by.
+pub trait HostExtensionBase {
+    const ID: &'static CStr;
+    /// Older draft or compatibility identifiers, queried in order
+    /// should the host not know of [`HostExtensionBase::ID`].
+    const COMPAT_IDS: &'static [&'static CStr] = &[];
+}
+/// A typed wrapper over a host-side extension
+pub trait HostExtension<'host>: AbstractPrototype<'host, Base: HostExtensionBase> + Sized {
+    fn from_base(host: Host<'host>, base: &'host Self::Base) -> Self;
+}
+
+#[derive(Clone, Copy)]
+pub struct Host<'host> {
+    raw: &'host clap_host,
+}
+impl<'host> Host<'host> {
+    /// # Safety
+    ///
+    /// `raw` must be the host handed to the plugin, whose functions are called as is.
+    #[inline]
+    pub unsafe fn from_raw(raw: &'host clap_host) -> Host<'host> {
+        Host { raw }
+    }
+    #[inline]
+    pub fn as_raw(&self) -> &'host clap_host {
+        self.raw
+    }
+    /// The CLAP version the host implements
+    #[inline]
+    pub fn clap_version(&self) -> clap_version {
+        self.raw.clap_version
+    }
+    #[inline]
+    pub fn name(&self) -> Option<&'host HostName> {
+        unsafe { from_nullable(self.raw.name, HostName::from_ptr) }
+    }
+    #[inline]
+    pub fn vendor(&self) -> Option<&'host HostVendor> {
+        unsafe { from_nullable(self.raw.vendor, HostVendor::from_ptr) }
+    }
+    #[inline]
+    pub fn url(&self) -> Option<&'host HostURL> {
+        unsafe { from_nullable(self.raw.url, HostURL::from_ptr) }
+    }
+    #[inline]
+    pub fn version(&self) -> Option<&'host HostVersion> {
+        unsafe { from_nullable(self.raw.version, HostVersion::from_ptr) }
+    }
+    /// `thread-safe`
+    ///
+    /// Request the host to deactivate and then reactivate the plugin.
+    /// The operation may be delayed by the host.
+    pub fn request_restart(&self) {
+        if let Some(request_restart) = self.raw.request_restart {
+            unsafe { request_restart(self.raw) };
+        }
+    }
+    /// `thread-safe`
+    ///
+    /// Request the host to activate and start processing the plugin.
+    /// This is useful if you have external IO and need to wake up the plugin from "sleep".
+    pub fn request_process(&self) {
+        if let Some(request_process) = self.raw.request_process {
+            unsafe { request_process(self.raw) };
+        }
+    }
+    /// `thread-safe`
+    ///
+    /// Request the host to schedule a call to
+    /// [`PluginPrototype::on_main_thread`](crate::plugin::PluginPrototype::on_main_thread),
+    /// on the main thread.
+    pub fn request_callback(&self) {
+        if let Some(request_callback) = self.raw.request_callback {
+            unsafe { request_callback(self.raw) };
+        }
+    }
+    /// `thread-safe`
+    ///
+    /// Queries the vtable of an extension by its identifier, [`None`] if the host
+    /// does not support it.
+    pub fn get_extension_ptr(&self, id: &HostExtensionID) -> Option<*const c_void> {
+        let get_extension = self.raw.get_extension?;
+        let ext = unsafe { get_extension(self.raw, id.as_ptr()) };
+        (!ext.is_null()).then_some(ext)
+    }
+    /// `thread-safe`
+    ///
+    /// Queries the vtable of an extension, falling back to its compatibility identifiers.
+    pub fn get_extension_base<B>(&self) -> Option<&'host B>
+    where
+        B: HostExtensionBase,
+    {
+        core::iter::once(B::ID)
+            .chain(B::COMPAT_IDS.iter().copied())
+            .find_map(|id| self.get_extension_ptr(HostExtensionID::from_c_str(id)))
+            .and_then(|ext| unsafe { (ext as *const B).as_ref() })
+    }
+    /// `thread-safe`
+    ///
+    /// Queries an extension of the host, wrapped in its typed interface.
+    #[inline]
+    pub fn get_extension<T>(&self) -> Option<T>
+    where
+        T: HostExtension<'host>,
+    {
+        let base = self.get_extension_base::<T::Base>()?;
+        Some(T::from_base(*self, base))
+    }
+    /// Logs through `clap_host_log`, if the host supports it.
+    ///
+    /// Queries the host, so not to be called before `clap_plugin.init`.
+    #[inline]
+    pub fn logger(&self) -> Logger<'host> {
+        Logger::new(self.raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap_sys::ext::log::{CLAP_EXT_LOG, clap_host_log};
+    use clap_sys::version::CLAP_VERSION;
+
+    use super::*;
+
+    static LOG: clap_host_log = clap_host_log { log: None };
+
+    unsafe extern "C" fn get_extension(
+        _host: *const clap_host,
+        id: *const core::ffi::c_char,
+    ) -> *const c_void {
+        if unsafe { CStr::from_ptr(id) } == CLAP_EXT_LOG {
+            &LOG as *const _ as *const c_void
+        } else {
+            ::core::ptr::null()
+        }
+    }
+
+    #[test]
+    fn queries_host() {
+        let raw = clap_host {
+            clap_version: CLAP_VERSION,
+            host_data: ::core::ptr::null_mut(),
+            name: c"Host".as_ptr(),
+            vendor: ::core::ptr::null(),
+            url: c"".as_ptr(),
+            version: c"1.0".as_ptr(),
+            get_extension: Some(get_extension),
+            request_restart: None,
+            request_process: None,
+            request_callback: None,
+        };
+        let host = unsafe { Host::from_raw(&raw) };
+
+        assert!(host.name().is_some_and(|name| name == c"Host"));
+        assert!(host.vendor().is_none());
+        host.request_callback();
+        assert!(
+            host.get_extension_base::<clap_host_log>()
+                .is_some_and(|log| ::core::ptr::eq(log, &LOG))
+        );
+        assert!(
+            host.get_extension_ptr(HostExtensionID::from_c_str(c"clap.gui"))
+                .is_none()
+        );
+    }
+}
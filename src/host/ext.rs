@@ -0,0 +1,45 @@
+//! Host extensions
+//!
+//! Typed wrappers over the extensions a host may provide, queried with
+//! [`Host::get_extension`](crate::host::Host::get_extension). A function the
+//! host left null in its vtable is not called, and reported as a failure where
+//! the callback returns anything.
+
+pub mod gui;
+pub mod latency;
+pub mod params;
+pub mod posix_fd_support;
+pub mod state;
+pub mod tail;
+pub mod thread_check;
+pub mod timer_support;
+pub mod voice_info;
+
+/// Declares the wrapper of a host extension vtable, and ties the vtable to its identifier.
+macro_rules! host_extension {
+    ($(#[$meta:meta])* $name:ident($base:ty) = $id:expr) => {
+        $(#[$meta])*
+        #[derive(Clone, Copy)]
+        pub struct $name<'host> {
+            host: $crate::host::Host<'host>,
+            base: &'host $base,
+        }
+        impl<'host> $crate::AbstractPrototype<'host> for $name<'host> {
+            type Base = $base;
+            #[inline]
+            fn as_base(&self) -> &Self::Base {
+                self.base
+            }
+        }
+        impl<'host> $crate::host::HostExtension<'host> for $name<'host> {
+            #[inline]
+            fn from_base(host: $crate::host::Host<'host>, base: &'host Self::Base) -> Self {
+                $name { host, base }
+            }
+        }
+        impl $crate::host::HostExtensionBase for $base {
+            const ID: &'static ::core::ffi::CStr = $id;
+        }
+    };
+}
+pub(crate) use host_extension;
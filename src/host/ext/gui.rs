@@ -0,0 +1,64 @@
+use clap_sys::ext::gui::{CLAP_EXT_GUI, clap_host_gui};
+
+use crate::host::ext::host_extension;
+
+host_extension! {
+    /// Lets the plugin drive its window through the host
+    HostGUI(clap_host_gui) = CLAP_EXT_GUI
+}
+impl HostGUI<'_> {
+    /// `thread-safe` & `!floating`
+    ///
+    /// The host should call get_resize_hints() again.
+    pub fn resize_hints_changed(&self) {
+        if let Some(resize_hints_changed) = self.base.resize_hints_changed {
+            unsafe { resize_hints_changed(self.host.as_raw()) };
+        }
+    }
+    /// `thread-safe` & `!floating`
+    ///
+    /// Request the host to resize the client area to width, height.
+    /// Return true if the new size is accepted, false otherwise.
+    /// The host doesn't have to call set_size().
+    ///
+    /// Note: if not called from the main thread, then a return value simply means that the host
+    /// acknowledged the request and will process it asynchronously. If the request then can't be
+    /// satisfied then the host will call set_size() to revert the operation.
+    pub fn request_resize(&self, width: u32, height: u32) -> bool {
+        let Some(request_resize) = self.base.request_resize else {
+            return false;
+        };
+        unsafe { request_resize(self.host.as_raw(), width, height) }
+    }
+    /// `thread-safe`
+    ///
+    /// Request the host to show the plugin gui.
+    /// Return true on success, false otherwise.
+    pub fn request_show(&self) -> bool {
+        let Some(request_show) = self.base.request_show else {
+            return false;
+        };
+        unsafe { request_show(self.host.as_raw()) }
+    }
+    /// `thread-safe`
+    ///
+    /// Request the host to hide the plugin gui.
+    /// Return true on success, false otherwise.
+    pub fn request_hide(&self) -> bool {
+        let Some(request_hide) = self.base.request_hide else {
+            return false;
+        };
+        unsafe { request_hide(self.host.as_raw()) }
+    }
+    /// `thread-safe`
+    ///
+    /// The floating window has been closed, or the connection to the gui has been lost.
+    ///
+    /// If was_destroyed is true, then the host must call clap_plugin_gui->destroy() to acknowledge
+    /// the gui destruction.
+    pub fn closed(&self, was_destroyed: bool) {
+        if let Some(closed) = self.base.closed {
+            unsafe { closed(self.host.as_raw(), was_destroyed) };
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use clap_sys::ext::latency::{CLAP_EXT_LATENCY, clap_host_latency};
+
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+host_extension! {
+    HostLatency(clap_host_latency) = CLAP_EXT_LATENCY
+}
+impl HostLatency<'_> {
+    /// `main-thread` & `being-activated`
+    ///
+    /// Tell the host that the latency changed.
+    /// The latency is only allowed to change during plugin->activate.
+    /// If the plugin is activated, call host->request_restart()
+    pub fn changed(&self, _main_thread: MainThread) {
+        if let Some(changed) = self.base.changed {
+            unsafe { changed(self.host.as_raw()) };
+        }
+    }
+}
@@ -0,0 +1,72 @@
+use clap_sys::ext::params::{
+    CLAP_EXT_PARAMS, CLAP_PARAM_CLEAR_ALL, CLAP_PARAM_CLEAR_AUTOMATIONS,
+    CLAP_PARAM_CLEAR_MODULATIONS, CLAP_PARAM_RESCAN_ALL, CLAP_PARAM_RESCAN_INFO,
+    CLAP_PARAM_RESCAN_TEXT, CLAP_PARAM_RESCAN_VALUES, clap_host_params, clap_param_clear_flags,
+    clap_param_rescan_flags,
+};
+use clap_sys::id::clap_id;
+
+use crate::ext::clap_flags;
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+clap_flags! {
+    /// What the host is to rescan of the parameters, see [`HostParams::rescan`]
+    ParamRescanFlags(clap_param_rescan_flags) {
+        /// The values changed, without the parameters being adjusted
+        VALUES = CLAP_PARAM_RESCAN_VALUES;
+        /// The text of the values changed, without the values themselves
+        TEXT = CLAP_PARAM_RESCAN_TEXT;
+        /// The flags, names, modules or ranges changed, only while the plugin is deactivated
+        INFO = CLAP_PARAM_RESCAN_INFO;
+        /// Parameters were added, removed or rearranged, only while the plugin is deactivated
+        ALL = CLAP_PARAM_RESCAN_ALL;
+    }
+}
+clap_flags! {
+    /// What the host is to clear of a parameter, see [`HostParams::clear`]
+    ParamClearFlags(clap_param_clear_flags) {
+        /// Every reference to the parameter
+        ALL = CLAP_PARAM_CLEAR_ALL;
+        AUTOMATIONS = CLAP_PARAM_CLEAR_AUTOMATIONS;
+        MODULATIONS = CLAP_PARAM_CLEAR_MODULATIONS;
+    }
+}
+
+host_extension! {
+    /// Lets the plugin tell the host about changes to its parameters
+    HostParams(clap_host_params) = CLAP_EXT_PARAMS
+}
+impl HostParams<'_> {
+    /// `main-thread`
+    ///
+    /// Rescan the full list of parameters according to the flags.
+    pub fn rescan(&self, _main_thread: MainThread, flags: ParamRescanFlags) {
+        if let Some(rescan) = self.base.rescan {
+            unsafe { rescan(self.host.as_raw(), flags.bits()) };
+        }
+    }
+    /// `main-thread`
+    ///
+    /// Clears references to a parameter, like automation or modulation.
+    pub fn clear(&self, _main_thread: MainThread, param_id: clap_id, flags: ParamClearFlags) {
+        if let Some(clear) = self.base.clear {
+            unsafe { clear(self.host.as_raw(), param_id, flags.bits()) };
+        }
+    }
+    /// `thread-safe` & `!audio-thread`
+    ///
+    /// Request a parameter flush.
+    ///
+    /// The host will then schedule a call to either:
+    /// - `clap_plugin.process()`
+    /// - [`PluginParamsPrototype::flush`](crate::ext::params::PluginParamsPrototype::flush)
+    ///
+    /// This function is always safe to use and should not be called from an `audio-thread` as the
+    /// plugin would already be within process() or flush().
+    pub fn request_flush(&self) {
+        if let Some(request_flush) = self.base.request_flush {
+            unsafe { request_flush(self.host.as_raw()) };
+        }
+    }
+}
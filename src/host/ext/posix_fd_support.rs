@@ -0,0 +1,63 @@
+use clap_sys::ext::posix_fd_support::{CLAP_EXT_POSIX_FD_SUPPORT, clap_host_posix_fd_support};
+
+use crate::ext::posix_fd_support::PosixFdFlags;
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+host_extension! {
+    /// Lets the plugin have its file descriptors polled by the host's event loop
+    HostPosixFdSupport(clap_host_posix_fd_support) = CLAP_EXT_POSIX_FD_SUPPORT
+}
+impl HostPosixFdSupport<'_> {
+    /// `main-thread`
+    ///
+    /// Starts polling `fd` for `flags`, returns false if the host could not.
+    pub fn register_fd(&self, _main_thread: MainThread, fd: i32, flags: PosixFdFlags) -> bool {
+        let Some(register_fd) = self.base.register_fd else {
+            return false;
+        };
+        unsafe { register_fd(self.host.as_raw(), fd, flags.bits()) }
+    }
+    /// `main-thread`
+    ///
+    /// Changes what the registered `fd` is polled for, returns false if the host could not.
+    pub fn modify_fd(&self, _main_thread: MainThread, fd: i32, flags: PosixFdFlags) -> bool {
+        let Some(modify_fd) = self.base.modify_fd else {
+            return false;
+        };
+        unsafe { modify_fd(self.host.as_raw(), fd, flags.bits()) }
+    }
+    /// `main-thread`
+    ///
+    /// Stops polling `fd`, returns false if the host did not know of it.
+    pub fn unregister_fd(&self, _main_thread: MainThread, fd: i32) -> bool {
+        let Some(unregister_fd) = self.base.unregister_fd else {
+            return false;
+        };
+        unsafe { unregister_fd(self.host.as_raw(), fd) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::StubHost;
+
+    #[test]
+    fn passes_fds_through() {
+        let host = StubHost::leak();
+        let fd_support = host.host().get_extension::<HostPosixFdSupport>().unwrap();
+        let main_thread = unsafe { MainThread::new_unchecked() };
+        let flags = PosixFdFlags::READ | PosixFdFlags::WRITE;
+
+        assert!(fd_support.register_fd(main_thread, 5, flags));
+        assert!(fd_support.modify_fd(main_thread, 5, PosixFdFlags::ERROR));
+        assert!(fd_support.unregister_fd(main_thread, 5));
+        assert_eq!(*host.registered_fds.lock().unwrap(), [(5, flags.bits())]);
+        assert_eq!(
+            *host.modified_fds.lock().unwrap(),
+            [(5, PosixFdFlags::ERROR.bits())]
+        );
+        assert_eq!(*host.unregistered_fds.lock().unwrap(), [5]);
+    }
+}
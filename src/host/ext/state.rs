@@ -0,0 +1,19 @@
+use clap_sys::ext::state::{CLAP_EXT_STATE, clap_host_state};
+
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+host_extension! {
+    HostState(clap_host_state) = CLAP_EXT_STATE
+}
+impl HostState<'_> {
+    /// `main-thread`
+    ///
+    /// Tell the host that the plugin state has changed and should be saved again.
+    /// If a parameter value changes, then it is implicit that the state is dirty.
+    pub fn mark_dirty(&self, _main_thread: MainThread) {
+        if let Some(mark_dirty) = self.base.mark_dirty {
+            unsafe { mark_dirty(self.host.as_raw()) };
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use clap_sys::ext::tail::{CLAP_EXT_TAIL, clap_host_tail};
+
+use crate::host::ext::host_extension;
+use crate::thread::AudioThread;
+
+host_extension! {
+    HostTail(clap_host_tail) = CLAP_EXT_TAIL
+}
+impl HostTail<'_> {
+    /// `audio-thread`
+    ///
+    /// Tell the host that the tail has changed.
+    pub fn changed(&self, _audio_thread: AudioThread) {
+        if let Some(changed) = self.base.changed {
+            unsafe { changed(self.host.as_raw()) };
+        }
+    }
+}
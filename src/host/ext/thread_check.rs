@@ -0,0 +1,24 @@
+use clap_sys::ext::thread_check::{CLAP_EXT_THREAD_CHECK, clap_host_thread_check};
+
+use crate::host::ext::host_extension;
+
+host_extension! {
+    /// Tells which of the threads of the host the caller is on
+    HostThreadCheck(clap_host_thread_check) = CLAP_EXT_THREAD_CHECK
+}
+impl HostThreadCheck<'_> {
+    /// `thread-safe`
+    ///
+    /// Returns true if "this" thread is the main thread, [`None`] if the host cannot tell.
+    pub fn is_main_thread(&self) -> Option<bool> {
+        let is_main_thread = self.base.is_main_thread?;
+        Some(unsafe { is_main_thread(self.host.as_raw()) })
+    }
+    /// `thread-safe`
+    ///
+    /// Returns true if "this" thread is one of the audio threads, [`None`] if the host cannot tell.
+    pub fn is_audio_thread(&self) -> Option<bool> {
+        let is_audio_thread = self.base.is_audio_thread?;
+        Some(unsafe { is_audio_thread(self.host.as_raw()) })
+    }
+}
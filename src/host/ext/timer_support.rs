@@ -0,0 +1,31 @@
+use clap_sys::ext::timer_support::{CLAP_EXT_TIMER_SUPPORT, clap_host_timer_support};
+use clap_sys::id::{CLAP_INVALID_ID, clap_id};
+
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+host_extension! {
+    /// Lets the plugin have the host call it back periodically on the main thread
+    HostTimerSupport(clap_host_timer_support) = CLAP_EXT_TIMER_SUPPORT
+}
+impl HostTimerSupport<'_> {
+    /// `main-thread`
+    ///
+    /// Registers a periodic timer, the host may adjust the period if it is too small.
+    /// Returns the id of the timer, [`None`] if the host refused it.
+    pub fn register_timer(&self, _main_thread: MainThread, period_ms: u32) -> Option<clap_id> {
+        let register_timer = self.base.register_timer?;
+        let mut timer_id = CLAP_INVALID_ID;
+        let registered = unsafe { register_timer(self.host.as_raw(), period_ms, &mut timer_id) };
+        (registered && timer_id != CLAP_INVALID_ID).then_some(timer_id)
+    }
+    /// `main-thread`
+    ///
+    /// Unregisters the timer `timer_id`, returns false if the host did not know of it.
+    pub fn unregister_timer(&self, _main_thread: MainThread, timer_id: clap_id) -> bool {
+        let Some(unregister_timer) = self.base.unregister_timer else {
+            return false;
+        };
+        unsafe { unregister_timer(self.host.as_raw(), timer_id) }
+    }
+}
@@ -0,0 +1,18 @@
+use clap_sys::ext::voice_info::{CLAP_EXT_VOICE_INFO, clap_host_voice_info};
+
+use crate::host::ext::host_extension;
+use crate::thread::MainThread;
+
+host_extension! {
+    HostVoiceInfo(clap_host_voice_info) = CLAP_EXT_VOICE_INFO
+}
+impl HostVoiceInfo<'_> {
+    /// `main-thread`
+    ///
+    /// Tell the host that the voice info has changed.
+    pub fn changed(&self, _main_thread: MainThread) {
+        if let Some(changed) = self.base.changed {
+            unsafe { changed(self.host.as_raw()) };
+        }
+    }
+}
@@ -0,0 +1,20 @@
+use core::ffi::c_char;
+
+::clap_proc_tools::ez_c_str! { HostName }
+::clap_proc_tools::ez_c_str! { HostVendor }
+::clap_proc_tools::ez_c_str! { HostURL }
+::clap_proc_tools::ez_c_str! { HostVersion }
+::clap_proc_tools::ez_c_str! { HostExtensionID }
+
+/// Reads one of the strings of `clap_host`, which a misbehaving host may leave null.
+///
+/// # Safety
+///
+/// `ptr` must be null, or a nul-terminated string valid for `'a`.
+#[inline]
+pub(crate) unsafe fn from_nullable<'a, T: ?Sized + 'a>(
+    ptr: *const c_char,
+    from_ptr: unsafe fn(*const c_char) -> &'a T,
+) -> Option<&'a T> {
+    (!ptr.is_null()).then(|| unsafe { from_ptr(ptr) })
+}
@@ -1,17 +1,26 @@
 pub use clap_proc_tools::entrypoint;
 
+// The macros name the crate by path, which the tests have to resolve as well
+#[cfg(test)]
+extern crate self as clap_prototype;
+
 use clap_sys::process::{
     CLAP_PROCESS_CONTINUE, CLAP_PROCESS_CONTINUE_IF_NOT_QUIET, CLAP_PROCESS_ERROR,
     CLAP_PROCESS_SLEEP, CLAP_PROCESS_TAIL, clap_process_status,
 };
-use core::mem::transmute;
 
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+pub mod entry;
+pub mod events;
 pub mod ext;
 pub mod factory;
+pub mod guard;
+pub mod host;
+pub mod log;
 pub mod plugin;
+pub mod process;
+#[cfg(test)]
+pub(crate) mod testing;
+pub mod thread;
 
 #[repr(transparent)]
 pub struct ProtoPtr<'host, E: AbstractPrototype<'host> + ?Sized>(
@@ -69,9 +78,14 @@ impl<A: ?Sized> ::core::ops::Deref for AbstractPointer<'_, A> {
 ///      HRESULT fail indicating negative numbers.
 ///   -  Thus, I suggest the encoding:
 ///
+/// ```text
+///   31  30 .. 16   15 .. 8   7 .. 0
+///   1   0          kind      entity
+/// ```
 ///
+/// Where an error without any further information is `CLAP_PROCESS_ERROR` itself.
 #[repr(i8)]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum EntityKind {
     Plugin = 0b00,
     Extension = 0b01,
@@ -80,74 +94,142 @@ pub enum EntityKind {
     // This can continue, but based on my current idea,
     // no further tha 0xFF
 }
-impl From<u8> for EntityKind {
-    fn from(value: u8) -> Self {
-        if value > 0b11 {
-            panic!("What are you doing thats like a WAY too big value. muh invariants");
+impl TryFrom<u8> for EntityKind {
+    type Error = DecodeError;
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b00 => Ok(EntityKind::Plugin),
+            0b01 => Ok(EntityKind::Extension),
+            0b10 => Ok(EntityKind::Host),
+            0b11 => Ok(EntityKind::Factory),
+            _ => Err(DecodeError::EntityKind(value)),
         }
-        // SAFETY: Look above. Explicit panic xd
-        unsafe { transmute(value) }
     }
 }
-impl From<i8> for EntityKind {
-    fn from(value: i8) -> Self {
-        if value < 0b00 {
-            panic!("THATS NEGATIVE BRUH IM USING THOSE NUMBERS");
-        }
-        EntityKind::from(value as u8)
+impl TryFrom<i8> for EntityKind {
+    type Error = DecodeError;
+    fn try_from(value: i8) -> Result<Self, Self::Error> {
+        EntityKind::try_from(value as u8)
     }
 }
 type ClapErrorKindCode = clap_process_status;
 pub const CLAP_ERROR_KIND_NOT_FOUND: ClapErrorKindCode = 0b00;
 pub const CLAP_ERROR_INIT_FAILED: ClapErrorKindCode = 0b01;
+pub const CLAP_ERROR_UNKNOWN: ClapErrorKindCode = u8::MAX as ClapErrorKindCode;
 #[repr(i8)]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ClapErrorKind {
     NotFound(EntityKind) = 0b00,
     InitFailed(EntityKind) = 0b01,
 
     Unknown(EntityKind) = u8::MAX as i8,
 }
+impl ClapErrorKind {
+    #[inline]
+    pub const fn code(&self) -> ClapErrorKindCode {
+        match self {
+            ClapErrorKind::NotFound(_) => CLAP_ERROR_KIND_NOT_FOUND,
+            ClapErrorKind::InitFailed(_) => CLAP_ERROR_INIT_FAILED,
+            ClapErrorKind::Unknown(_) => CLAP_ERROR_UNKNOWN,
+        }
+    }
+    #[inline]
+    pub const fn entity(&self) -> EntityKind {
+        match self {
+            ClapErrorKind::NotFound(entity)
+            | ClapErrorKind::InitFailed(entity)
+            | ClapErrorKind::Unknown(entity) => *entity,
+        }
+    }
+}
+/// The kind in the high byte, the entity in the low byte
 impl From<ClapErrorKind> for u16 {
     #[inline]
     fn from(value: ClapErrorKind) -> Self {
-        unsafe { core::mem::transmute(value) }
+        ((value.code() as u16) << 8) | value.entity() as u16
     }
 }
-// impl From<u16> for ClapErrorKind {
-//     fn from(value: u16) -> Self {
-//         let (hi, lo) = ((value & 0xFF00 >> 8) as u8, (value & 0x00FF) as u8);
-//         let ek = EntityKind::from(lo);
-
-//     }
-// }
-// impl ClapErrorKind {
-//     fn pack_to_dword(self) -> i16 {
-//         todo!()
-//     }
-// }
+impl TryFrom<u16> for ClapErrorKind {
+    type Error = DecodeError;
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        let [hi, lo] = value.to_be_bytes();
+        let entity = EntityKind::try_from(lo)?;
+        match hi as ClapErrorKindCode {
+            CLAP_ERROR_KIND_NOT_FOUND => Ok(ClapErrorKind::NotFound(entity)),
+            CLAP_ERROR_INIT_FAILED => Ok(ClapErrorKind::InitFailed(entity)),
+            CLAP_ERROR_UNKNOWN => Ok(ClapErrorKind::Unknown(entity)),
+            _ => Err(DecodeError::ErrorKind(hi)),
+        }
+    }
+}
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecodeError {
+    EntityKind(u8),
+    ErrorKind(u8),
+    ProcessStatus(clap_process_status),
+}
+impl ::core::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+        match self {
+            DecodeError::EntityKind(code) => write!(f, "invalid entity kind {code:#04x}"),
+            DecodeError::ErrorKind(code) => write!(f, "invalid error kind {code:#04x}"),
+            DecodeError::ProcessStatus(status) => write!(f, "invalid process status {status:#x}"),
+        }
+    }
+}
+impl ::std::error::Error for DecodeError {}
 #[repr(i32)]
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum ProcessResult {
     /// Keep going
     Continue = CLAP_PROCESS_CONTINUE,
     ContinueIfNeeded = CLAP_PROCESS_CONTINUE_IF_NOT_QUIET,
     Sleep = CLAP_PROCESS_SLEEP,
+    /// Keep going for as long as the plugin's [`TailLength`](crate::ext::tail::TailLength),
+    /// only meaningful along with the tail extension
     Tail = CLAP_PROCESS_TAIL,
-    /// Error type, just wait and see this is gonna be sweet
-    /// Might look like an anti-pattern at this point, but this way
-    /// the API will be closer-ish to Rust/C FFI shit
+    /// Failed, reported to the host as `CLAP_PROCESS_ERROR`.
+    /// The kind only travels through [`clap_process_status`] within the plugin,
+    /// see [`ProcessResult::host_status`].
     Err(Option<ClapErrorKind>) = CLAP_PROCESS_ERROR,
 }
-impl From<clap_process_status> for ProcessResult {
-    fn from(value: clap_process_status) -> Self {
+impl ProcessResult {
+    /// The status handed to the host, which only knows of a single error status
+    #[inline]
+    pub fn host_status(&self) -> clap_process_status {
+        match self {
+            ProcessResult::Err(_) => CLAP_PROCESS_ERROR,
+            result => clap_process_status::from(*result),
+        }
+    }
+}
+impl From<ProcessResult> for clap_process_status {
+    fn from(value: ProcessResult) -> Self {
         match value {
-            CLAP_PROCESS_CONTINUE => Self::Continue,
-            CLAP_PROCESS_CONTINUE_IF_NOT_QUIET => Self::ContinueIfNeeded,
-            CLAP_PROCESS_SLEEP => Self::Sleep,
-            CLAP_PROCESS_TAIL => Self::Tail,
-            CLAP_PROCESS_ERROR => todo!(),
-            _ => panic!("WHO TF RETURNED A PROCES STATUS NOT DEFINED BY CLAP"),
+            ProcessResult::Continue => CLAP_PROCESS_CONTINUE,
+            ProcessResult::ContinueIfNeeded => CLAP_PROCESS_CONTINUE_IF_NOT_QUIET,
+            ProcessResult::Sleep => CLAP_PROCESS_SLEEP,
+            ProcessResult::Tail => CLAP_PROCESS_TAIL,
+            ProcessResult::Err(None) => CLAP_PROCESS_ERROR,
+            ProcessResult::Err(Some(kind)) => i32::MIN | u16::from(kind) as clap_process_status,
+        }
+    }
+}
+impl TryFrom<clap_process_status> for ProcessResult {
+    type Error = DecodeError;
+    fn try_from(value: clap_process_status) -> Result<Self, Self::Error> {
+        match value {
+            CLAP_PROCESS_CONTINUE => Ok(Self::Continue),
+            CLAP_PROCESS_CONTINUE_IF_NOT_QUIET => Ok(Self::ContinueIfNeeded),
+            CLAP_PROCESS_SLEEP => Ok(Self::Sleep),
+            CLAP_PROCESS_TAIL => Ok(Self::Tail),
+            CLAP_PROCESS_ERROR => Ok(Self::Err(None)),
+            // The fail bit, with nothing but the error kind packed in the low word
+            _ if value & !(i32::MIN | 0xFFFF) == 0 && value < 0 => {
+                let kind = ClapErrorKind::try_from(value as u16)?;
+                Ok(Self::Err(Some(kind)))
+            }
+            _ => Err(DecodeError::ProcessStatus(value)),
         }
     }
 }
@@ -156,8 +238,40 @@ mod tests {
     use super::*;
 
     #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
+    fn process_result_round_trip() {
+        let results = [
+            ProcessResult::Continue,
+            ProcessResult::ContinueIfNeeded,
+            ProcessResult::Sleep,
+            ProcessResult::Tail,
+            ProcessResult::Err(None),
+            ProcessResult::Err(Some(ClapErrorKind::NotFound(EntityKind::Extension))),
+            ProcessResult::Err(Some(ClapErrorKind::InitFailed(EntityKind::Plugin))),
+            ProcessResult::Err(Some(ClapErrorKind::Unknown(EntityKind::Factory))),
+        ];
+        for result in results {
+            let status = clap_process_status::from(result);
+            assert_eq!(ProcessResult::try_from(status), Ok(result));
+        }
+        assert_eq!(
+            ProcessResult::Err(Some(ClapErrorKind::Unknown(EntityKind::Host))).host_status(),
+            CLAP_PROCESS_ERROR
+        );
+        assert_eq!(
+            ProcessResult::try_from(i32::MIN | 0x0004),
+            Err(DecodeError::EntityKind(0x04))
+        );
+        assert_eq!(
+            ProcessResult::try_from(i32::MIN | 0x0200),
+            Err(DecodeError::ErrorKind(0x02))
+        );
+        assert_eq!(
+            ProcessResult::try_from(i32::MIN | 0x10000),
+            Err(DecodeError::ProcessStatus(i32::MIN | 0x10000))
+        );
+        assert_eq!(
+            ProcessResult::try_from(42),
+            Err(DecodeError::ProcessStatus(42))
+        );
     }
 }
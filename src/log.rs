@@ -0,0 +1,152 @@
+//! Log
+//!
+//! Diagnostics of the crate, and of the plugins built on it. A [`Logger`] forwards
+//! them to the host through `clap_host_log` when the host provides it, everything
+//! else goes to the fallback [`Sink`], stderr unless set otherwise with [`set_sink`].
+//!
+//! Messages below the severity set with [`set_min_severity`] are dropped before
+//! being formatted.
+//!
+//! Logging formats and allocates, so it has no place on the `audio-thread`,
+//! [`Logger::audio`] is compiled out unless the `audio-thread-log` feature is enabled.
+
+use core::fmt::Arguments;
+use std::ffi::{CStr, CString};
+use std::fs::File;
+use std::io::Write;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicI32, Ordering};
+
+use clap_sys::ext::log::{
+    CLAP_EXT_LOG, CLAP_LOG_DEBUG, CLAP_LOG_ERROR, CLAP_LOG_FATAL, CLAP_LOG_HOST_MISBEHAVING,
+    CLAP_LOG_INFO, CLAP_LOG_PLUGIN_MISBEHAVING, CLAP_LOG_WARNING, clap_host_log, clap_log_severity,
+};
+use clap_sys::host::clap_host;
+
+use crate::host::{Host, HostExtensionBase};
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Debug = CLAP_LOG_DEBUG,
+    Info = CLAP_LOG_INFO,
+    Warning = CLAP_LOG_WARNING,
+    Error = CLAP_LOG_ERROR,
+    Fatal = CLAP_LOG_FATAL,
+    /// The host broke the protocol, like passing a null pointer
+    HostMisbehaving = CLAP_LOG_HOST_MISBEHAVING,
+    /// The plugin broke the protocol, like panicking in a callback
+    PluginMisbehaving = CLAP_LOG_PLUGIN_MISBEHAVING,
+}
+impl Severity {
+    #[inline]
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Debug => "debug",
+            Severity::Info => "info",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+            Severity::Fatal => "fatal",
+            Severity::HostMisbehaving => "host-misbehaving",
+            Severity::PluginMisbehaving => "plugin-misbehaving",
+        }
+    }
+}
+impl From<Severity> for clap_log_severity {
+    #[inline]
+    fn from(value: Severity) -> Self {
+        value as clap_log_severity
+    }
+}
+
+impl HostExtensionBase for clap_host_log {
+    const ID: &'static CStr = CLAP_EXT_LOG;
+}
+
+/// Where messages go when the host does not take them
+pub enum Sink {
+    Stderr,
+    File(File),
+    None,
+}
+
+static SINK: Mutex<Sink> = Mutex::new(Sink::Stderr);
+static MIN_SEVERITY: AtomicI32 = AtomicI32::new(CLAP_LOG_INFO);
+
+pub fn set_sink(sink: Sink) {
+    *SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = sink;
+}
+pub fn set_min_severity(severity: Severity) {
+    MIN_SEVERITY.store(severity.into(), Ordering::Relaxed);
+}
+#[inline]
+pub fn enabled(severity: Severity) -> bool {
+    clap_log_severity::from(severity) >= MIN_SEVERITY.load(Ordering::Relaxed)
+}
+/// Logs to the fallback sink, for when no host is at hand.
+pub fn log(severity: Severity, args: Arguments<'_>) {
+    if enabled(severity) {
+        write_sink(severity, args);
+    }
+}
+fn write_sink(severity: Severity, args: Arguments<'_>) {
+    let mut sink = SINK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let line = format!("[clap-prototype {}] {args}\n", severity.as_str());
+    // There is nowhere left to report a failing sink to
+    let _ = match &mut *sink {
+        Sink::Stderr => std::io::stderr().write_all(line.as_bytes()),
+        Sink::File(file) => file.write_all(line.as_bytes()),
+        Sink::None => Ok(()),
+    };
+}
+
+/// Logs to the host, or to the fallback sink if it does not support `clap_host_log`
+#[derive(Clone, Copy)]
+pub struct Logger<'host> {
+    host: Option<&'host clap_host>,
+    host_log: Option<&'host clap_host_log>,
+}
+impl<'host> Logger<'host> {
+    /// `thread-safe`
+    pub fn new(host: &'host clap_host) -> Logger<'host> {
+        let host_log = unsafe { Host::from_raw(host) }.get_extension_base::<clap_host_log>();
+        Logger {
+            host: Some(host),
+            host_log,
+        }
+    }
+    /// A logger writing to the fallback sink only
+    #[inline]
+    pub const fn fallback() -> Logger<'static> {
+        Logger {
+            host: None,
+            host_log: None,
+        }
+    }
+    /// `thread-safe`
+    pub fn log(&self, severity: Severity, args: Arguments<'_>) {
+        if !enabled(severity) {
+            return;
+        }
+        let Some((host, log)) = self
+            .host
+            .zip(self.host_log.and_then(|host_log| host_log.log))
+        else {
+            return write_sink(severity, args);
+        };
+        let Ok(msg) = CString::new(args.to_string()) else {
+            return write_sink(severity, args);
+        };
+        unsafe { log(host, severity.into(), msg.as_ptr()) };
+    }
+    /// `audio-thread`
+    ///
+    /// Like [`Logger::log`], only compiled in with the `audio-thread-log` feature.
+    #[inline(always)]
+    pub fn audio(&self, severity: Severity, args: Arguments<'_>) {
+        #[cfg(feature = "audio-thread-log")]
+        self.log(severity, args);
+        #[cfg(not(feature = "audio-thread-log"))]
+        let _ = (severity, args);
+    }
+}
@@ -1,15 +1,25 @@
 pub(crate) mod descriptor;
 pub mod features;
+pub(crate) mod instance;
 pub(crate) mod str_types;
 
-use clap_sys::process::{clap_process, clap_process_status};
+use core::ffi::{c_char, c_void};
+
+pub use clap_proc_tools::plugin_descriptor;
+use clap_sys::process::{CLAP_PROCESS_ERROR, clap_process, clap_process_status};
 pub use descriptor::PluginDescriptor;
+use instance::PluginInstance;
 pub use str_types::*;
 
-use crate::AbstractPrototype;
-use crate::ext::ExtensionPrototype;
+use crate::ext::{ExtensionPrototype, ExtensionRegistry};
+use crate::guard;
+use crate::log::{self, Severity};
+use crate::process::ProcessContext;
+use crate::thread::{self, AudioThread, MainThread};
+use crate::{AbstractPrototype, ProcessResult};
 
-use clap_sys::plugin::clap_plugin;
+use clap_sys::ext::tail::{CLAP_EXT_TAIL, clap_plugin_tail};
+use clap_sys::plugin::{clap_plugin, clap_plugin_descriptor};
 
 pub trait PluginPrototype<'host>: AbstractPrototype<'host, Base = clap_plugin> {
     /// `main-thread`
@@ -22,7 +32,10 @@ pub trait PluginPrototype<'host>: AbstractPrototype<'host, Base = clap_plugin> {
     /// in init you have complete access to the host
     /// and host extensions, so clap related setup activities should be done here rather than in
     /// create_plugin.
-    fn init(&mut self) -> bool;
+    ///
+    /// Like every other callback, it takes `&self`: state set up here lives behind
+    /// interior mutability, as extensions may hold the plugin at the same time.
+    fn init(&self, main_thread: MainThread) -> bool;
     /// `main-thread` & `!active`
     ///
     /// Activate and deactivate the plugin.
@@ -32,19 +45,25 @@ pub trait PluginPrototype<'host>: AbstractPrototype<'host, Base = clap_plugin> {
     /// In this call the plugin may call host-provided methods marked `being-activated`.
     /// Once activated the latency and port configuration must remain constant, until deactivation.
     /// Returns true on success.
-    fn activate(&self, sample_rate: f64, min_frames_count: u32, max_frames_count: u32) -> bool;
+    fn activate(
+        &self,
+        main_thread: MainThread,
+        sample_rate: f64,
+        min_frames_count: u32,
+        max_frames_count: u32,
+    ) -> bool;
     /// `main-thread` & `active`
-    fn deactivate(&self);
+    fn deactivate(&self, main_thread: MainThread);
     /// `audio-thread` & `active` & `!processing`
     ///
     /// Call start processing before processing.
     /// Returns true on success.
-    fn start_processing(&self) -> bool;
+    fn start_processing(&self, audio_thread: AudioThread) -> bool;
 
     /// `audio-thread` & `active` & `processing`
     ///
     /// Call stop processing before sending the plugin to sleep.
-    fn stop_processing(&self);
+    fn stop_processing(&self, audio_thread: AudioThread);
     /// `audio-thread` & `active`
     ///
     /// - Clears all buffers, performs a full reset of the processing state (filters, oscillators,
@@ -52,17 +71,273 @@ pub trait PluginPrototype<'host>: AbstractPrototype<'host, Base = clap_plugin> {
     /// - The parameter's value remain unchanged.
     /// - [clap_process::steady_time] may jump backward.
     ///
-    fn reset(&mut self);
+    fn reset(&self, audio_thread: AudioThread);
     /// `audio-thread` & `active` & `processing`
     ///
     /// process audio, events, ...
     ///
-    /// All the pointers coming from [`clap_process`] and its nested attributes,
+    /// The audio buffers and everything else borrowed from the [`ProcessContext`],
     /// are valid until [`PluginPrototype::process`] returns.
-    fn process(&self, process_details: &clap_process) -> Option<clap_process_status>;
+    ///
+    /// Any [`ProcessResult::Err`] is reported to the host as `CLAP_PROCESS_ERROR`.
+    /// [`ProcessResult::Tail`] needs the tail extension, without it it is reported as
+    /// [`ProcessResult::ContinueIfNeeded`], and with an empty tail as [`ProcessResult::Sleep`].
+    fn process(
+        &self,
+        audio_thread: AudioThread,
+        process_context: &mut ProcessContext<'_>,
+    ) -> ProcessResult;
+    /// `main-thread`
+    ///
+    /// Called by the host on the main thread in response to a previous call to
+    /// `clap_host::request_callback`.
+    fn on_main_thread(&self, _main_thread: MainThread) {}
+    /// Whether a panic in any callback poisons the plugin, after which the host
+    /// only sees failures until it destroys it, see [`guard`](crate::guard).
+    const POISON_ON_PANIC: bool = false;
+}
+
+/// Where a plugin is in its activation cycle, see [`PluginPrototype::activate`]
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActivationState {
+    Inactive,
+    /// Within [`PluginPrototype::activate`], where `being-activated` host methods may be called
+    BeingActivated,
+    Active,
+}
+/// Where `plugin` is in its activation cycle, [`None`] if it was not created by a factory.
+pub fn activation_state<'host, P>(plugin: &P) -> Option<ActivationState>
+where
+    P: PluginPrototype<'host>,
+{
+    PluginInstance::of(plugin).map(PluginInstance::activation)
 }
 
 pub trait HasExtension<'host, B>: PluginPrototype<'host> {
     type ExtensionType: ExtensionPrototype<'host, Parent = Self, Base = B>;
     fn get_extension(&self) -> &Self::ExtensionType;
 }
+fn get_plugin<'host, 'plugin, P>(ptr: *const clap_plugin) -> Option<&'plugin P>
+where
+    P: PluginPrototype<'host> + 'plugin,
+    'host: 'plugin,
+{
+    let plugin = unsafe { ptr.as_ref() }?;
+    unsafe { (plugin.plugin_data as *const P).as_ref() }
+}
+unsafe extern "C" fn init<'host, P>(plugin: *const clap_plugin) -> bool
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.init", || {
+        if let Some(instance) = PluginInstance::<P>::from_plugin(plugin) {
+            instance.init_logger();
+        }
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin.init");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return false;
+        };
+        p.init(main_thread)
+    })
+}
+unsafe extern "C" fn destroy<'host, P>(plugin: *const clap_plugin)
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::call("clap_plugin.destroy", || {
+        let Some(instance) = PluginInstance::<P>::reclaim(plugin) else {
+            log::log(
+                Severity::HostMisbehaving,
+                format_args!("destroy of an unknown or already destroyed plugin"),
+            );
+            return;
+        };
+        // Give extensions the host left alive a chance to release their
+        // resources while the plugin is still whole
+        instance.teardown(plugin);
+        drop(instance);
+    })
+}
+unsafe extern "C" fn activate<'host, P>(
+    plugin: *const clap_plugin,
+    sample_rate: f64,
+    min_frames_count: u32,
+    max_frames_count: u32,
+) -> bool
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    let instance = PluginInstance::<P>::from_plugin(plugin);
+    let activated = guard::plugin_call::<P, _, _>(plugin, "clap_plugin.activate", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin.activate");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return false;
+        };
+        if let Some(instance) = instance {
+            instance.set_activation(ActivationState::BeingActivated);
+        }
+        p.activate(main_thread, sample_rate, min_frames_count, max_frames_count)
+    });
+    // Also leaves `BeingActivated` behind when `activate` panicked
+    if let Some(instance) = instance {
+        instance.set_activation(if activated {
+            ActivationState::Active
+        } else {
+            ActivationState::Inactive
+        });
+    }
+    activated
+}
+unsafe extern "C" fn deactivate<'host, P>(plugin: *const clap_plugin)
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.deactivate", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin.deactivate");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return;
+        };
+        p.deactivate(main_thread)
+    });
+    if let Some(instance) = PluginInstance::<P>::from_plugin(plugin) {
+        instance.set_activation(ActivationState::Inactive);
+    }
+}
+unsafe extern "C" fn start_processing<'host, P>(plugin: *const clap_plugin) -> bool
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.start_processing", || {
+        let audio_thread = thread::audio_thread::<P>(plugin, "clap_plugin.start_processing");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return false;
+        };
+        p.start_processing(audio_thread)
+    })
+}
+unsafe extern "C" fn stop_processing<'host, P>(plugin: *const clap_plugin)
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.stop_processing", || {
+        let audio_thread = thread::audio_thread::<P>(plugin, "clap_plugin.stop_processing");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return;
+        };
+        p.stop_processing(audio_thread)
+    })
+}
+unsafe extern "C" fn reset<'host, P>(plugin: *const clap_plugin)
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.reset", || {
+        let audio_thread = thread::audio_thread::<P>(plugin, "clap_plugin.reset");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return;
+        };
+        p.reset(audio_thread)
+    })
+}
+unsafe extern "C" fn process<'host, P>(
+    plugin: *const clap_plugin,
+    process: *const clap_process,
+) -> clap_process_status
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.process", || {
+        let audio_thread = thread::audio_thread::<P>(plugin, "clap_plugin.process");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return CLAP_PROCESS_ERROR;
+        };
+        let Some(process) = (unsafe { process.as_ref() }) else {
+            return CLAP_PROCESS_ERROR;
+        };
+        // SAFETY: The host keeps the buffers valid until process returns
+        let mut process_context = unsafe { ProcessContext::from_raw(process) };
+        let result = p.process(audio_thread, &mut process_context);
+        match result {
+            ProcessResult::Err(err) => {
+                PluginInstance::<P>::logger_of(plugin)
+                    .audio(Severity::Error, format_args!("process failed: {err:?}"));
+            }
+            ProcessResult::Tail => {
+                let tail = p
+                    .lookup(PluginExtensionID::from_c_str(CLAP_EXT_TAIL))
+                    .and_then(|tail| unsafe { (tail as *const clap_plugin_tail).as_ref() });
+                let Some(get_tail) = tail.and_then(|tail| tail.get) else {
+                    PluginInstance::<P>::logger_of(plugin).audio(
+                        Severity::PluginMisbehaving,
+                        format_args!("process returned Tail without the tail extension"),
+                    );
+                    return ProcessResult::ContinueIfNeeded.host_status();
+                };
+                // Without a tail to play out, there is nothing to keep processing for
+                if unsafe { get_tail(plugin) } == 0 {
+                    return ProcessResult::Sleep.host_status();
+                }
+            }
+            _ => {}
+        }
+        result.host_status()
+    })
+}
+unsafe extern "C" fn get_extension<'host, P>(
+    plugin: *const clap_plugin,
+    id: *const c_char,
+) -> *const c_void
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.get_extension", || {
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return ::core::ptr::null();
+        };
+        if id.is_null() {
+            return ::core::ptr::null();
+        }
+        let id = unsafe { PluginExtensionID::from_ptr(id) };
+        p.lookup(id).unwrap_or(::core::ptr::null())
+    })
+}
+unsafe extern "C" fn on_main_thread<'host, P>(plugin: *const clap_plugin)
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    guard::plugin_call::<P, _, _>(plugin, "clap_plugin.on_main_thread", || {
+        let main_thread = thread::main_thread::<P>(plugin, "clap_plugin.on_main_thread");
+        let Some(p) = get_plugin::<P>(plugin) else {
+            return;
+        };
+        p.on_main_thread(main_thread)
+    })
+}
+/// Builds the `clap_plugin` for an implementor of [`PluginPrototype`].
+///
+/// The result is meant to be stored as the first field of the plugin, and
+/// is what [`AbstractPrototype::as_base`] should return. `plugin_data` is
+/// left null, it is filled in by [`factory::create_plugin`](crate::factory)
+/// once the plugin has been moved to its final address.
+///
+/// The plugin is dropped when the host calls `destroy`.
+pub const fn vtable<'host, P>(descriptor: &'static clap_plugin_descriptor) -> clap_plugin
+where
+    P: PluginPrototype<'host> + ExtensionRegistry<'host>,
+{
+    clap_plugin {
+        desc: descriptor,
+        plugin_data: ::core::ptr::null_mut(),
+        init: Some(init::<'host, P>),
+        destroy: Some(destroy::<'host, P>),
+        activate: Some(activate::<'host, P>),
+        deactivate: Some(deactivate::<'host, P>),
+        start_processing: Some(start_processing::<'host, P>),
+        stop_processing: Some(stop_processing::<'host, P>),
+        reset: Some(reset::<'host, P>),
+        process: Some(process::<'host, P>),
+        get_extension: Some(get_extension::<'host, P>),
+        on_main_thread: Some(on_main_thread::<'host, P>),
+    }
+}
@@ -16,8 +16,8 @@ pub struct PluginDescriptor<'desc> {
     pub features: RawPluginFeatureSet,
 }
 impl<'desc> PluginDescriptor<'desc> {
+    /// A null list of features is read as an empty one.
     pub fn from_raw(raw: &'desc clap_plugin_descriptor) -> PluginDescriptor<'desc> {
-        println!("CONSTRUCTING PLUGIN_DESCRIPTOR {raw:#?}");
         Self {
             framework_version: raw.clap_version,
             id: unsafe { PluginID::from_ptr(raw.id) },
@@ -27,13 +27,13 @@ impl<'desc> PluginDescriptor<'desc> {
             manual_url: unsafe { PluginURL::from_ptr(raw.manual_url) },
             support_url: unsafe { PluginURL::from_ptr(raw.support_url) },
             version: unsafe { PluginVersion::from_ptr(raw.version) },
-            description: unsafe { PluginDescription::from_ptr(raw.version) },
+            description: unsafe { PluginDescription::from_ptr(raw.description) },
             features: RawPluginFeatureSet::from_ptr(raw.features)
-                .expect("failure to parse features"),
+                .unwrap_or(RawPluginFeatureSet::empty()),
         }
     }
     pub fn into_raw(self) -> clap_plugin_descriptor {
-        let raw = clap_plugin_descriptor {
+        clap_plugin_descriptor {
             clap_version: self.framework_version,
             id: self.id.as_ptr(),
             name: self.name.as_ptr(),
@@ -44,9 +44,7 @@ impl<'desc> PluginDescriptor<'desc> {
             version: self.version.as_ptr(),
             description: self.description.as_ptr(),
             features: self.features.as_ptr(),
-        };
-        println!("RAW DESCRIPTOR: {raw:#?}");
-        raw
+        }
     }
 }
 impl From<PluginDescriptor<'_>> for clap_plugin_descriptor {
@@ -54,3 +52,53 @@ impl From<PluginDescriptor<'_>> for clap_plugin_descriptor {
         value.into_raw()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use core::ffi::CStr;
+
+    use super::*;
+    use crate::plugin::features::PluginFeatureKind;
+    use crate::plugin::plugin_descriptor;
+
+    #[test]
+    fn from_macro() {
+        let raw = plugin_descriptor! {
+            id: "com.example.gain",
+            name: c"Gain",
+            version: "1.0.0",
+            features: [AudioEffect, "example:custom"],
+        };
+        let descriptor = PluginDescriptor::from_raw(raw);
+        assert!(*descriptor.id == *c"com.example.gain");
+        assert!(*descriptor.name == *c"Gain");
+        assert!(*descriptor.vendor == *c"");
+        let features = descriptor.features.as_slice();
+        assert_eq!(features.len(), 2);
+        let feature = |n: usize| unsafe { CStr::from_ptr(*raw.features.add(n)) };
+        assert_eq!(feature(0), PluginFeatureKind::AudioEffect.as_c_str());
+        assert_eq!(feature(1), c"example:custom");
+    }
+
+    #[test]
+    fn without_features() {
+        let raw = plugin_descriptor! {
+            id: "com.example.gain",
+            name: "Gain",
+            version: "1.0.0",
+        };
+        assert!(
+            PluginDescriptor::from_raw(raw)
+                .features
+                .as_slice()
+                .is_empty()
+        );
+        let null_features = clap_plugin_descriptor {
+            features: ::core::ptr::null(),
+            ..*raw
+        };
+        let descriptor = PluginDescriptor::from_raw(&null_features);
+        assert!(descriptor.features.as_slice().is_empty());
+        assert!(!descriptor.into_raw().features.is_null());
+    }
+}
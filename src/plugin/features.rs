@@ -1,5 +1,5 @@
 use core::borrow::Borrow;
-use core::ffi::CStr;
+use core::ffi::{CStr, c_char};
 use std::sync::Arc;
 
 use crate::plugin::str_types::PluginFeature;
@@ -52,7 +52,8 @@ impl From<PluginFeatureKind> for Arc<PluginFeature> {
     }
 }
 impl PluginFeatureKind {
-    fn as_c_str(&self) -> &'static CStr {
+    #[inline]
+    pub const fn as_c_str(&self) -> &'static CStr {
         use PluginFeatureKind::*;
         use clap_sys::plugin_features::*;
         match self {
@@ -103,3 +104,24 @@ impl Borrow<CStr> for PluginFeatureKind {
         self.as_c_str()
     }
 }
+
+/// A null-terminated list of features, laid out as `clap_plugin_descriptor.features`
+/// expects it, built by [`plugin_descriptor!`](crate::plugin::plugin_descriptor).
+#[repr(transparent)]
+pub struct StaticFeatureSet<const N: usize>([*const c_char; N]);
+// SAFETY: The features are immutable, 'static strings
+unsafe impl<const N: usize> Sync for StaticFeatureSet<N> {}
+impl<const N: usize> StaticFeatureSet<N> {
+    /// # Safety
+    ///
+    /// Every pointer but the last must point to a `'static`, nul-terminated string,
+    /// the last one must be null.
+    #[inline]
+    pub const unsafe fn new(features: [*const c_char; N]) -> Self {
+        StaticFeatureSet(features)
+    }
+    #[inline]
+    pub const fn as_ptr(&self) -> *const *const c_char {
+        self.0.as_ptr()
+    }
+}
@@ -0,0 +1,196 @@
+//! Ownership of the plugins handed out by the factory
+//!
+//! Every plugin created through [`factory::create_plugin`](crate::factory) is
+//! moved into a [`PluginInstance`], which is leaked and stored in
+//! `clap_plugin.plugin_data`, and reclaimed by `clap_plugin.destroy`.
+//!
+//! The plugin is the first field of the `#[repr(C)]` instance, and its
+//! `clap_plugin` the first field of the plugin, so the instance, the plugin
+//! and the `clap_plugin` handed to the host all share one address. This lets
+//! `plugin_data` keep being read as a pointer to the plugin itself by the
+//! extension thunks.
+//!
+//! The instance also carries the [`Host`] it was created for, its [`Logger`],
+//! and whether the plugin was poisoned by a panic, see
+//! [`guard`](crate::guard).
+//!
+//! The host may not be queried for its extensions before `clap_plugin.init`,
+//! so the logger only looks up `clap_host_log` from there on, and logs to the
+//! fallback sink until then.
+
+use core::ffi::CStr;
+use std::sync::atomic::{AtomicBool, AtomicU8, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+use clap_sys::plugin::clap_plugin;
+
+use crate::host::Host;
+use crate::log::Logger;
+use crate::plugin::ActivationState;
+
+/// A thunk to run against the plugin before it is dropped,
+/// like `clap_plugin_gui.destroy` for a GUI the host never destroyed.
+pub(crate) type Teardown = unsafe extern "C" fn(plugin: *const clap_plugin);
+
+/// Addresses of the instances which have not yet been destroyed
+static LIVE_INSTANCES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+#[repr(C)]
+pub(crate) struct PluginInstance<P> {
+    pub(crate) plugin: P,
+    teardown: Mutex<Vec<(&'static CStr, Teardown)>>,
+    poisoned: AtomicBool,
+    activation: AtomicU8,
+    /// The host outlives every plugin it created
+    host: Host<'static>,
+    /// Set by `clap_plugin.init`
+    logger: OnceLock<Logger<'static>>,
+}
+impl<P> PluginInstance<P> {
+    /// Leaks the plugin, registering it as a live instance.
+    pub(crate) fn leak<'a>(plugin: P, host: Host<'static>) -> &'a mut PluginInstance<P> {
+        let instance = Box::leak(Box::new(PluginInstance {
+            plugin,
+            teardown: Mutex::new(Vec::new()),
+            poisoned: AtomicBool::new(false),
+            activation: AtomicU8::new(ActivationState::Inactive as u8),
+            host,
+            logger: OnceLock::new(),
+        }));
+        let addr = instance as *mut PluginInstance<P> as usize;
+        LIVE_INSTANCES
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(addr);
+        instance
+    }
+    /// Recovers the instance of a plugin, without checking whether it is still alive.
+    pub(crate) fn from_plugin<'a>(ptr: *const clap_plugin) -> Option<&'a PluginInstance<P>> {
+        let plugin = unsafe { ptr.as_ref() }?;
+        unsafe { (plugin.plugin_data as *const PluginInstance<P>).as_ref() }
+    }
+    /// Recovers the instance of a plugin from the plugin itself, [`None`] if it
+    /// was not created by a factory.
+    pub(crate) fn of<'a, 'host>(plugin: &'a P) -> Option<&'a PluginInstance<P>>
+    where
+        P: crate::AbstractPrototype<'host, Base = clap_plugin>,
+    {
+        Self::from_plugin(plugin.as_base())
+    }
+    /// The logger of the instance of a plugin, the fallback one if there is no instance.
+    pub(crate) fn logger_of(ptr: *const clap_plugin) -> Logger<'static> {
+        Self::from_plugin(ptr).map_or(Logger::fallback(), PluginInstance::logger)
+    }
+    #[inline]
+    pub(crate) fn host(&self) -> Host<'_> {
+        self.host
+    }
+    /// The logger of the host once the plugin is initialized, the fallback one before.
+    #[inline]
+    pub(crate) fn logger(&self) -> Logger<'static> {
+        self.logger.get().copied().unwrap_or(Logger::fallback())
+    }
+    /// `main-thread`
+    ///
+    /// Looks up the logger of the host, from `clap_plugin.init` on.
+    pub(crate) fn init_logger(&self) {
+        self.logger.get_or_init(|| self.host.logger());
+    }
+    /// Takes back ownership of the instance of a plugin.
+    ///
+    /// Returns [`None`] if the instance was already reclaimed, so that a
+    /// second `destroy` from the host does not free it twice.
+    pub(crate) fn reclaim(ptr: *const clap_plugin) -> Option<Box<PluginInstance<P>>> {
+        // The `clap_plugin` lives at the start of the instance, so a pointer
+        // to it is only dereferenced once known to belong to a live instance
+        Self::reclaim_raw(ptr as *mut PluginInstance<P>)
+    }
+    /// Like [`PluginInstance::reclaim`], starting from the address of the instance.
+    pub(crate) fn reclaim_raw(instance: *mut PluginInstance<P>) -> Option<Box<PluginInstance<P>>> {
+        let addr = instance as usize;
+        let mut live = LIVE_INSTANCES
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let position = live.iter().position(|live_addr| *live_addr == addr)?;
+        live.swap_remove(position);
+        // SAFETY: The address was produced by `PluginInstance::leak`,
+        // and has just been removed from the live instances.
+        Some(unsafe { Box::from_raw(addr as *mut PluginInstance<P>) })
+    }
+    #[inline]
+    pub(crate) fn is_poisoned(&self) -> bool {
+        self.poisoned.load(Ordering::Acquire)
+    }
+    #[inline]
+    pub(crate) fn poison(&self) {
+        self.poisoned.store(true, Ordering::Release);
+    }
+    #[inline]
+    pub(crate) fn activation(&self) -> ActivationState {
+        match self.activation.load(Ordering::Acquire) {
+            state if state == ActivationState::BeingActivated as u8 => {
+                ActivationState::BeingActivated
+            }
+            state if state == ActivationState::Active as u8 => ActivationState::Active,
+            _ => ActivationState::Inactive,
+        }
+    }
+    #[inline]
+    pub(crate) fn set_activation(&self, state: ActivationState) {
+        self.activation.store(state as u8, Ordering::Release);
+    }
+    /// `main-thread`
+    ///
+    /// Registers a teardown for the extension `id`, replacing any previous one.
+    pub(crate) fn on_teardown(&self, id: &'static CStr, teardown: Teardown) {
+        let mut pending = self.pending_teardown();
+        pending.retain(|(pending_id, _)| *pending_id != id);
+        pending.push((id, teardown));
+    }
+    /// `main-thread`
+    ///
+    /// Removes the teardown for the extension `id`, if any.
+    pub(crate) fn cancel_teardown(&self, id: &'static CStr) {
+        self.pending_teardown()
+            .retain(|(pending_id, _)| *pending_id != id);
+    }
+    /// `main-thread`
+    ///
+    /// Runs, and forgets, the pending teardowns.
+    pub(crate) fn teardown(&self, ptr: *const clap_plugin) {
+        let pending = ::core::mem::take(&mut *self.pending_teardown());
+        for (_, teardown) in pending {
+            unsafe { teardown(ptr) };
+        }
+    }
+    fn pending_teardown(&self) -> std::sync::MutexGuard<'_, Vec<(&'static CStr, Teardown)>> {
+        self.teardown
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap_sys::ext::log::CLAP_EXT_LOG;
+
+    use crate::testing::{StubHost, TestPlugin};
+
+    #[test]
+    fn looks_up_the_logger_from_init() {
+        let host = StubHost::leak();
+        let plugin = TestPlugin::create(host);
+        assert!(host.queried_extensions.lock().unwrap().is_empty());
+        unsafe {
+            assert!((*plugin).init.unwrap()(plugin));
+            assert!(
+                host.queried_extensions
+                    .lock()
+                    .unwrap()
+                    .iter()
+                    .any(|id| id.as_c_str() == CLAP_EXT_LOG)
+            );
+            (*plugin).destroy.unwrap()(plugin);
+        }
+    }
+}
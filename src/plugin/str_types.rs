@@ -6,6 +6,8 @@ use std::sync::Arc;
 
 use clap_sys::string_sizes::{CLAP_NAME_SIZE, CLAP_PATH_SIZE};
 
+use crate::plugin::features::StaticFeatureSet;
+
 pub trait PluginStringSlice {
     type Pointer;
     fn as_ptr(&self) -> Self::Pointer;
@@ -86,6 +88,12 @@ fn try_from_inner<'s, T: ?Sized, const N: usize>(
     // Tail return
     Err(FromPtrError::MissingNul)
 }
+impl TryFrom<*const c_char> for &PluginID {
+    type Error = FromPtrError;
+    fn try_from(value: *const c_char) -> Result<Self, Self::Error> {
+        try_from_inner::<PluginID, CLAP_NAME_SIZE>(value, PluginID::from_ptr)
+    }
+}
 impl TryFrom<*const c_char> for &PluginName {
     type Error = FromPtrError;
     fn try_from(value: *const c_char) -> Result<Self, Self::Error> {
@@ -191,7 +199,12 @@ impl RawPluginFeatureSet {
             row += 1;
         }
 
-        if row == 0 { None } else { Some(row) }
+        Some(row)
+    }
+    /// The set without any feature
+    pub const fn empty() -> RawPluginFeatureSet {
+        static EMPTY: StaticFeatureSet<1> = unsafe { StaticFeatureSet::new([::core::ptr::null()]) };
+        RawPluginFeatureSet(EMPTY.as_ptr())
     }
     pub const fn from_ptr(ptr: *const *const c_char) -> Option<RawPluginFeatureSet> {
         if RawPluginFeatureSet(ptr).calculate_length().is_none() {
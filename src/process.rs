@@ -0,0 +1,699 @@
+//! Process
+//!
+//! Safe view over the `clap_process` handed to
+//! [`PluginPrototype::process`](crate::plugin::PluginPrototype::process).
+//!
+//! Audio is exposed per port and channel as slices of `frames_count` samples,
+//! in either 32 or 64 bit, depending on which of `data32`/`data64` the host
+//! provided for the port.
+//!
+//! A host may process in-place, in which case an input channel and an output
+//! channel share the same buffer. Output slices are only handed out through a
+//! mutable borrow of the [`ProcessContext`], so an input slice can never be
+//! alive while its in-place output is being written to, use
+//! [`ProcessContext::channel_pair`] to work on both at once.
+//!
+//! [`ProcessContext::blocks`] splits the block at the time of its events, so
+//! they can be applied on the exact sample they occur at.
+
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::num::NonZeroU32;
+use core::ops::Range;
+
+use clap_sys::audio_buffer::clap_audio_buffer;
+use clap_sys::events::{CLAP_CORE_EVENT_SPACE_ID, clap_event_header, clap_event_transport};
+use clap_sys::process::clap_process;
+
+use crate::events::{Event, InputEvents, OutputEvents};
+use crate::ext::audio_ports::PluginAudioPortsPrototype;
+use crate::thread::MainThread;
+
+/// A sample type the host may provide audio buffers in
+pub trait Sample: Copy + 'static {
+    /// The channel pointers of the buffer for this sample type, may be null.
+    fn channels(buffer: &clap_audio_buffer) -> *mut *mut Self;
+}
+impl Sample for f32 {
+    #[inline]
+    fn channels(buffer: &clap_audio_buffer) -> *mut *mut Self {
+        buffer.data32
+    }
+}
+impl Sample for f64 {
+    #[inline]
+    fn channels(buffer: &clap_audio_buffer) -> *mut *mut Self {
+        buffer.data64
+    }
+}
+
+/// An input channel, and the output channel it is processed into
+pub enum ChannelPair<'buf, S: Sample> {
+    /// The host processes in-place, the buffer holds the input
+    /// and the output is to be written over it.
+    InPlace(&'buf mut [S]),
+    Separate {
+        input: &'buf [S],
+        output: &'buf mut [S],
+    },
+}
+
+/// The channel count of every port, as advertised by the audio-ports extension
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AudioPortLayout {
+    pub inputs: Vec<u32>,
+    pub outputs: Vec<u32>,
+}
+impl AudioPortLayout {
+    /// `main-thread`
+    ///
+    /// Queries the ports of the extension, meant to be called when activating,
+    /// as the port configuration may not change while the plugin is active.
+    pub fn capture<'host, A>(main_thread: MainThread, ports: &A) -> AudioPortLayout
+    where
+        A: PluginAudioPortsPrototype<'host>,
+    {
+        let channel_counts = |is_input| {
+            (0..ports.count(main_thread, is_input))
+                .map(|index| {
+                    ports
+                        .get(main_thread, index, is_input)
+                        .map_or(0, |info| info.channel_count)
+                })
+                .collect()
+        };
+        AudioPortLayout {
+            inputs: channel_counts(true),
+            outputs: channel_counts(false),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ProcessContextError {
+    PortCount {
+        is_input: bool,
+        expected: u32,
+        found: u32,
+    },
+    ChannelCount {
+        is_input: bool,
+        port_index: u32,
+        expected: u32,
+        found: u32,
+    },
+    MissingBuffer {
+        is_input: bool,
+        port_index: u32,
+    },
+}
+impl Display for ProcessContextError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let direction = |is_input: &bool| if *is_input { "input" } else { "output" };
+        match self {
+            ProcessContextError::PortCount {
+                is_input,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "expected {expected} {} ports, host provided {found}",
+                direction(is_input)
+            )),
+            ProcessContextError::ChannelCount {
+                is_input,
+                port_index,
+                expected,
+                found,
+            } => f.write_fmt(format_args!(
+                "expected {expected} channels on {} port {port_index}, host provided {found}",
+                direction(is_input)
+            )),
+            ProcessContextError::MissingBuffer {
+                is_input,
+                port_index,
+            } => f.write_fmt(format_args!(
+                "host provided neither 32 nor 64 bit buffers on {} port {port_index}",
+                direction(is_input)
+            )),
+        }
+    }
+}
+
+pub struct ProcessContext<'process> {
+    raw: &'process clap_process,
+}
+impl<'process> ProcessContext<'process> {
+    /// # Safety
+    ///
+    /// All the pointers of `raw` must be valid for `'process`, as guaranteed by
+    /// the host for the duration of `clap_plugin.process`.
+    #[inline]
+    pub unsafe fn from_raw(raw: &'process clap_process) -> ProcessContext<'process> {
+        ProcessContext { raw }
+    }
+    #[inline]
+    pub fn as_raw(&self) -> &'process clap_process {
+        self.raw
+    }
+    /// Number of frames of every channel in this block
+    #[inline]
+    pub fn frames_count(&self) -> u32 {
+        self.raw.frames_count
+    }
+    /// A steady sample time counter, if the host provides one.
+    ///
+    /// Can be used to compute the sleep duration between two process calls.
+    #[inline]
+    pub fn steady_time(&self) -> Option<u64> {
+        u64::try_from(self.raw.steady_time).ok()
+    }
+    /// The transport at sample 0, [`None`] if free-running
+    #[inline]
+    pub fn transport(&self) -> Option<&'process clap_event_transport> {
+        unsafe { self.raw.transport.as_ref() }
+    }
+    /// The events of this block, sorted by time
+    #[inline]
+    pub fn in_events(&self) -> InputEvents<'process> {
+        match unsafe { self.raw.in_events.as_ref() } {
+            Some(in_events) => unsafe { InputEvents::from_raw(in_events) },
+            None => InputEvents::empty(),
+        }
+    }
+    /// Where to send events to the host, like parameter changes made in the block
+    #[inline]
+    pub fn out_events(&self) -> OutputEvents<'process> {
+        match unsafe { self.raw.out_events.as_ref() } {
+            Some(out_events) => unsafe { OutputEvents::from_raw(out_events) },
+            None => OutputEvents::unsupported(),
+        }
+    }
+    #[inline]
+    fn buffers(&self, is_input: bool) -> &'process [clap_audio_buffer] {
+        let (ptr, count) = if is_input {
+            (self.raw.audio_inputs, self.raw.audio_inputs_count)
+        } else {
+            (
+                self.raw.audio_outputs as *const _,
+                self.raw.audio_outputs_count,
+            )
+        };
+        if ptr.is_null() {
+            return &[];
+        }
+        unsafe { core::slice::from_raw_parts(ptr, count as usize) }
+    }
+    #[inline]
+    fn buffer(&self, is_input: bool, port_index: u32) -> Option<&'process clap_audio_buffer> {
+        self.buffers(is_input).get(port_index as usize)
+    }
+    #[inline]
+    fn channel_ptr<S: Sample>(
+        &self,
+        is_input: bool,
+        port_index: u32,
+        channel: u32,
+    ) -> Option<*mut S> {
+        let buffer = self.buffer(is_input, port_index)?;
+        if channel >= buffer.channel_count {
+            return None;
+        }
+        let channels = S::channels(buffer);
+        if channels.is_null() {
+            return None;
+        }
+        let ptr = unsafe { *channels.add(channel as usize) };
+        (!ptr.is_null()).then_some(ptr)
+    }
+    #[inline]
+    pub fn input_port_count(&self) -> u32 {
+        self.buffers(true).len() as u32
+    }
+    #[inline]
+    pub fn output_port_count(&self) -> u32 {
+        self.buffers(false).len() as u32
+    }
+    #[inline]
+    pub fn input_channel_count(&self, port_index: u32) -> Option<u32> {
+        self.buffer(true, port_index)
+            .map(|buffer| buffer.channel_count)
+    }
+    #[inline]
+    pub fn output_channel_count(&self, port_index: u32) -> Option<u32> {
+        self.buffer(false, port_index)
+            .map(|buffer| buffer.channel_count)
+    }
+    /// The latency from and to the audio interface, of an input port
+    #[inline]
+    pub fn input_latency(&self, port_index: u32) -> Option<u32> {
+        self.buffer(true, port_index).map(|buffer| buffer.latency)
+    }
+    /// Whether the host provided this port in 64 bit
+    #[inline]
+    pub fn is_input_64bit(&self, port_index: u32) -> bool {
+        self.buffer(true, port_index)
+            .is_some_and(|buffer| !buffer.data64.is_null())
+    }
+    /// Whether the host expects this port in 64 bit
+    #[inline]
+    pub fn is_output_64bit(&self, port_index: u32) -> bool {
+        self.buffer(false, port_index)
+            .is_some_and(|buffer| !buffer.data64.is_null())
+    }
+    pub fn input<S: Sample>(&self, port_index: u32, channel: u32) -> Option<&[S]> {
+        let ptr = self.channel_ptr::<S>(true, port_index, channel)?;
+        Some(unsafe { core::slice::from_raw_parts(ptr, self.frames_count() as usize) })
+    }
+    pub fn output<S: Sample>(&mut self, port_index: u32, channel: u32) -> Option<&mut [S]> {
+        let ptr = self.channel_ptr::<S>(false, port_index, channel)?;
+        Some(unsafe { core::slice::from_raw_parts_mut(ptr, self.frames_count() as usize) })
+    }
+    /// Every channel of an output port, in order.
+    ///
+    /// [`None`] if the host made some of the channels share memory, which CLAP does
+    /// not rule out, the channels then have to be taken one at a time with
+    /// [`ProcessContext::output`].
+    pub fn output_channels<S: Sample>(
+        &mut self,
+        port_index: u32,
+    ) -> Option<impl Iterator<Item = &mut [S]> + '_> {
+        let channel_count = self.output_channel_count(port_index).unwrap_or(0);
+        let frames_count = self.frames_count() as usize;
+        if !self.are_output_channels_disjoint::<S>(port_index, channel_count, frames_count) {
+            return None;
+        }
+        let this = &*self;
+        Some(
+            (0..channel_count)
+                .filter_map(move |channel| this.channel_ptr::<S>(false, port_index, channel))
+                // SAFETY: The channels were just checked not to overlap
+                .map(move |ptr| unsafe { core::slice::from_raw_parts_mut(ptr, frames_count) }),
+        )
+    }
+    /// Whether no two channels of an output port overlap, compares every pair
+    /// as ports have few channels, and the audio thread must not allocate.
+    fn are_output_channels_disjoint<S: Sample>(
+        &self,
+        port_index: u32,
+        channel_count: u32,
+        frames_count: usize,
+    ) -> bool {
+        let len = frames_count * ::core::mem::size_of::<S>();
+        let start = |channel| {
+            self.channel_ptr::<S>(false, port_index, channel)
+                .map(|ptr| ptr as usize)
+        };
+        (0..channel_count).all(|a| {
+            (a + 1..channel_count).all(|b| match (start(a), start(b)) {
+                (Some(a), Some(b)) => a + len <= b || b + len <= a,
+                _ => true,
+            })
+        })
+    }
+    /// Whether the host is processing this channel in-place
+    pub fn is_in_place<S: Sample>(
+        &self,
+        input_port_index: u32,
+        output_port_index: u32,
+        channel: u32,
+    ) -> bool {
+        let input = self.channel_ptr::<S>(true, input_port_index, channel);
+        let output = self.channel_ptr::<S>(false, output_port_index, channel);
+        input.is_some() && input == output
+    }
+    /// [`None`] if either channel is missing, or if the host made them partly overlap.
+    pub fn channel_pair<S: Sample>(
+        &mut self,
+        input_port_index: u32,
+        output_port_index: u32,
+        channel: u32,
+    ) -> Option<ChannelPair<'_, S>> {
+        let frames_count = self.frames_count() as usize;
+        let input = self.channel_ptr::<S>(true, input_port_index, channel)?;
+        let output = self.channel_ptr::<S>(false, output_port_index, channel)?;
+        if ::core::ptr::eq(input, output) {
+            let output = unsafe { core::slice::from_raw_parts_mut(output, frames_count) };
+            return Some(ChannelPair::InPlace(output));
+        }
+        // Neither in-place nor separate, the buffers partly overlap
+        let len = frames_count * ::core::mem::size_of::<S>();
+        let (input_start, output_start) = (input as usize, output as usize);
+        if input_start < output_start + len && output_start < input_start + len {
+            return None;
+        }
+        let output = unsafe { core::slice::from_raw_parts_mut(output, frames_count) };
+        let input = unsafe { core::slice::from_raw_parts(input, frames_count) };
+        Some(ChannelPair::Separate { input, output })
+    }
+    /// Whether every sample of the input channel holds the same value
+    #[inline]
+    pub fn is_input_constant(&self, port_index: u32, channel: u32) -> bool {
+        self.buffer(true, port_index)
+            .is_some_and(|buffer| channel < 64 && buffer.constant_mask & (1 << channel) != 0)
+    }
+    #[inline]
+    pub fn is_output_constant(&self, port_index: u32, channel: u32) -> bool {
+        self.buffer(false, port_index)
+            .is_some_and(|buffer| channel < 64 && buffer.constant_mask & (1 << channel) != 0)
+    }
+    /// Tell the host whether every sample of the output channel holds the same value
+    pub fn set_output_constant(&mut self, port_index: u32, channel: u32, is_constant: bool) {
+        if channel >= 64 || port_index >= self.raw.audio_outputs_count {
+            return;
+        }
+        if self.raw.audio_outputs.is_null() {
+            return;
+        }
+        let buffer = unsafe { &mut *self.raw.audio_outputs.add(port_index as usize) };
+        if is_constant {
+            buffer.constant_mask |= 1 << channel;
+        } else {
+            buffer.constant_mask &= !(1 << channel);
+        }
+    }
+    /// Splits the block at the time of every event, and every `max_block_size` frames.
+    pub fn blocks(&mut self, max_block_size: Option<NonZeroU32>) -> Blocks<'_, 'process> {
+        Blocks {
+            ctx: self,
+            max_block_size,
+            position: 0,
+            next_event: 0,
+        }
+    }
+    /// Checks the buffers of the host against the ports the plugin advertised.
+    pub fn validate(&self, layout: &AudioPortLayout) -> Result<(), ProcessContextError> {
+        for (is_input, expected) in [(true, &layout.inputs), (false, &layout.outputs)] {
+            let buffers = self.buffers(is_input);
+            if buffers.len() != expected.len() {
+                return Err(ProcessContextError::PortCount {
+                    is_input,
+                    expected: expected.len() as u32,
+                    found: buffers.len() as u32,
+                });
+            }
+            for (port_index, (buffer, expected)) in buffers.iter().zip(expected).enumerate() {
+                let port_index = port_index as u32;
+                if buffer.channel_count != *expected {
+                    return Err(ProcessContextError::ChannelCount {
+                        is_input,
+                        port_index,
+                        expected: *expected,
+                        found: buffer.channel_count,
+                    });
+                }
+                if buffer.channel_count > 0 && buffer.data32.is_null() && buffer.data64.is_null() {
+                    return Err(ProcessContextError::MissingBuffer {
+                        is_input,
+                        port_index,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The sub-blocks of a [`ProcessContext`], see [`ProcessContext::blocks`]
+pub struct Blocks<'ctx, 'process> {
+    ctx: &'ctx mut ProcessContext<'process>,
+    max_block_size: Option<NonZeroU32>,
+    position: u32,
+    next_event: u32,
+}
+impl<'process> Blocks<'_, 'process> {
+    /// The time of the event at `index`, events past the end of the block
+    /// are treated as occurring on its last frame.
+    #[inline]
+    fn event_time(&self, events: &InputEvents<'process>, index: u32) -> u32 {
+        let last = self.ctx.frames_count().saturating_sub(1);
+        events
+            .get(index)
+            .map_or(last, |header| header.time.min(last))
+    }
+    /// The next sub-block, [`None`] once every frame and every event was handed out.
+    ///
+    /// A sub-block holds the events occurring at its start, along with any event
+    /// the host placed earlier out of order, and ends at the time of the next event.
+    pub fn next_block(&mut self) -> Option<Block<'_, 'process>> {
+        let frames_count = self.ctx.frames_count();
+        let events = self.ctx.in_events();
+        let events_count = events.len();
+        let start = self.position;
+        let first_event = self.next_event;
+        if start >= frames_count && first_event >= events_count {
+            return None;
+        }
+        let mut next_event = first_event;
+        while next_event < events_count && self.event_time(&events, next_event) <= start {
+            next_event += 1;
+        }
+        let mut end = frames_count;
+        if next_event < events_count {
+            end = end.min(self.event_time(&events, next_event));
+        }
+        if let Some(max_block_size) = self.max_block_size {
+            end = end.min(start.saturating_add(max_block_size.get()));
+        }
+        self.position = end;
+        self.next_event = next_event;
+        Some(Block {
+            ctx: self.ctx,
+            frames: start..end,
+            events: first_event..next_event,
+        })
+    }
+}
+
+/// A range of frames of a [`ProcessContext`], and the events occurring at its start
+pub struct Block<'ctx, 'process> {
+    ctx: &'ctx mut ProcessContext<'process>,
+    frames: Range<u32>,
+    events: Range<u32>,
+}
+impl<'process> Block<'_, 'process> {
+    /// The frames of the whole [`ProcessContext`] this block covers
+    #[inline]
+    pub fn frames(&self) -> Range<u32> {
+        self.frames.clone()
+    }
+    #[inline]
+    pub fn frames_count(&self) -> u32 {
+        self.frames.end - self.frames.start
+    }
+    /// The core events to apply before processing this block, their time
+    /// is relative to the whole [`ProcessContext`].
+    pub fn events(&self) -> impl Iterator<Item = Event<'process>> + 'process {
+        let in_events = self.ctx.in_events();
+        self.events
+            .clone()
+            .filter_map(move |index| in_events.get(index))
+            .filter(|header| header.space_id == CLAP_CORE_EVENT_SPACE_ID)
+            // SAFETY: The header comes from the host's list
+            .map(|header| unsafe { Event::from_header(header) })
+    }
+    /// The headers of every event to apply before processing this block, of any event space
+    pub fn event_headers(&self) -> impl Iterator<Item = &'process clap_event_header> + 'process {
+        let in_events = self.ctx.in_events();
+        self.events
+            .clone()
+            .filter_map(move |index| in_events.get(index))
+    }
+    #[inline]
+    pub fn out_events(&self) -> OutputEvents<'process> {
+        self.ctx.out_events()
+    }
+    #[inline]
+    pub fn context(&self) -> &ProcessContext<'process> {
+        self.ctx
+    }
+    #[inline]
+    fn range(&self) -> Range<usize> {
+        self.frames.start as usize..self.frames.end as usize
+    }
+    pub fn input<S: Sample>(&self, port_index: u32, channel: u32) -> Option<&[S]> {
+        let range = self.range();
+        self.ctx
+            .input(port_index, channel)
+            .map(|input| &input[range])
+    }
+    pub fn output<S: Sample>(&mut self, port_index: u32, channel: u32) -> Option<&mut [S]> {
+        let range = self.range();
+        self.ctx
+            .output(port_index, channel)
+            .map(|output| &mut output[range])
+    }
+    /// Every channel of an output port, in order.
+    ///
+    /// [`None`] if the host made some of the channels share memory,
+    /// see [`ProcessContext::output_channels`].
+    pub fn output_channels<S: Sample>(
+        &mut self,
+        port_index: u32,
+    ) -> Option<impl Iterator<Item = &mut [S]> + '_> {
+        let range = self.range();
+        self.ctx
+            .output_channels(port_index)
+            .map(move |outputs| outputs.map(move |output| &mut output[range.clone()]))
+    }
+    pub fn channel_pair<S: Sample>(
+        &mut self,
+        input_port_index: u32,
+        output_port_index: u32,
+        channel: u32,
+    ) -> Option<ChannelPair<'_, S>> {
+        let range = self.range();
+        let pair = self
+            .ctx
+            .channel_pair(input_port_index, output_port_index, channel)?;
+        Some(match pair {
+            ChannelPair::InPlace(buffer) => ChannelPair::InPlace(&mut buffer[range]),
+            ChannelPair::Separate { input, output } => ChannelPair::Separate {
+                input: &input[range.clone()],
+                output: &mut output[range],
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::mem::size_of;
+
+    use super::*;
+    use crate::events::test_input_events;
+
+    fn buffer(channels: &mut [*mut f32]) -> clap_audio_buffer {
+        clap_audio_buffer {
+            data32: channels.as_mut_ptr(),
+            data64: ::core::ptr::null_mut(),
+            channel_count: channels.len() as u32,
+            latency: 0,
+            constant_mask: 0,
+        }
+    }
+    fn process(
+        inputs: &[clap_audio_buffer],
+        outputs: &mut [clap_audio_buffer],
+        frames_count: u32,
+    ) -> clap_process {
+        clap_process {
+            steady_time: -1,
+            frames_count,
+            transport: ::core::ptr::null(),
+            audio_inputs: inputs.as_ptr(),
+            audio_outputs: outputs.as_mut_ptr(),
+            audio_inputs_count: inputs.len() as u32,
+            audio_outputs_count: outputs.len() as u32,
+            in_events: ::core::ptr::null(),
+            out_events: ::core::ptr::null(),
+        }
+    }
+
+    #[test]
+    fn channel_pairs() {
+        let mut left = [1.0_f32; 4];
+        let mut right = [2.0_f32; 4];
+        let mut out_left = [0.0_f32; 4];
+        let mut in_channels = [left.as_mut_ptr(), right.as_mut_ptr()];
+        let mut out_channels = [out_left.as_mut_ptr(), right.as_mut_ptr()];
+        let inputs = [buffer(&mut in_channels)];
+        let mut outputs = [buffer(&mut out_channels)];
+        let raw = process(&inputs, &mut outputs, 4);
+        let mut ctx = unsafe { ProcessContext::from_raw(&raw) };
+
+        assert_eq!(ctx.steady_time(), None);
+        assert!(!ctx.is_in_place::<f32>(0, 0, 0));
+        assert!(ctx.is_in_place::<f32>(0, 0, 1));
+        assert!(ctx.input::<f64>(0, 0).is_none());
+        assert!(matches!(
+            ctx.channel_pair::<f32>(0, 0, 0),
+            Some(ChannelPair::Separate {
+                input: [1.0, ..],
+                ..
+            })
+        ));
+        assert!(matches!(
+            ctx.channel_pair::<f32>(0, 0, 1),
+            Some(ChannelPair::InPlace([2.0, ..]))
+        ));
+        for channel in ctx.output_channels::<f32>(0).unwrap() {
+            channel.fill(3.0);
+        }
+        ctx.set_output_constant(0, 1, true);
+        assert!(ctx.is_output_constant(0, 1));
+        assert_eq!(out_left, [3.0; 4]);
+    }
+    #[test]
+    fn aliased_output_channels() {
+        let mut shared = [0.0_f32; 4];
+        let mut out_channels = [shared.as_mut_ptr(), unsafe { shared.as_mut_ptr().add(2) }];
+        let mut outputs = [buffer(&mut out_channels)];
+        let raw = process(&[], &mut outputs, 4);
+        let mut ctx = unsafe { ProcessContext::from_raw(&raw) };
+        assert!(ctx.output_channels::<f32>(0).is_none());
+        assert!(ctx.output::<f32>(0, 1).is_some());
+
+        let mut in_channels = [unsafe { shared.as_mut_ptr().add(1) }];
+        let inputs = [buffer(&mut in_channels)];
+        let raw = process(&inputs, &mut outputs, 4);
+        let mut ctx = unsafe { ProcessContext::from_raw(&raw) };
+        assert!(ctx.channel_pair::<f32>(0, 0, 0).is_none());
+    }
+    #[test]
+    fn split_blocks() {
+        let mut out = [0.0_f32; 8];
+        let mut channels = [out.as_mut_ptr()];
+        let mut outputs = [buffer(&mut channels)];
+        let mut raw = process(&[], &mut outputs, 8);
+        let events: Vec<clap_event_header> = [0, 3, 3, 5, 9]
+            .into_iter()
+            .map(|time| clap_event_header {
+                size: size_of::<clap_event_header>() as u32,
+                time,
+                space_id: 1,
+                type_: 0,
+                flags: 0,
+            })
+            .collect();
+        let list: Vec<*const clap_event_header> =
+            events.iter().map(|header| header as *const _).collect();
+        let in_events = test_input_events(&list);
+        raw.in_events = &in_events;
+        let mut ctx = unsafe { ProcessContext::from_raw(&raw) };
+
+        let mut blocks = ctx.blocks(NonZeroU32::new(4));
+        let mut split = Vec::new();
+        while let Some(mut block) = blocks.next_block() {
+            let start = block.frames().start as f32;
+            block.output::<f32>(0, 0).unwrap().fill(start);
+            split.push((block.frames(), block.event_headers().count()));
+        }
+        assert_eq!(split, [(0..3, 1), (3..5, 2), (5..7, 1), (7..8, 1)]);
+        assert_eq!(out, [0.0, 0.0, 0.0, 3.0, 3.0, 5.0, 5.0, 7.0]);
+    }
+    #[test]
+    fn validate_layout() {
+        let mut left = [0.0_f32; 2];
+        let mut channels = [left.as_mut_ptr()];
+        let inputs = [buffer(&mut channels)];
+        let raw = process(&inputs, &mut [], 2);
+        let ctx = unsafe { ProcessContext::from_raw(&raw) };
+        let layout = AudioPortLayout {
+            inputs: vec![1],
+            outputs: vec![],
+        };
+        assert_eq!(ctx.validate(&layout), Ok(()));
+        let layout = AudioPortLayout {
+            inputs: vec![2],
+            outputs: vec![],
+        };
+        assert_eq!(
+            ctx.validate(&layout),
+            Err(ProcessContextError::ChannelCount {
+                is_input: true,
+                port_index: 0,
+                expected: 2,
+                found: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,503 @@
+//! Fixtures shared by the tests: a host recording what plugins ask of it, and a
+//! plugin implementing a handful of extensions, created the way a factory would.
+
+use core::ffi::{CStr, c_char, c_void};
+use std::ffi::CString;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+use clap_sys::ext::latency::{CLAP_EXT_LATENCY, clap_host_latency};
+use clap_sys::ext::posix_fd_support::{
+    CLAP_EXT_POSIX_FD_SUPPORT, clap_host_posix_fd_support, clap_posix_fd_flags,
+};
+use clap_sys::ext::thread_check::{CLAP_EXT_THREAD_CHECK, clap_host_thread_check};
+use clap_sys::ext::timer_support::{CLAP_EXT_TIMER_SUPPORT, clap_host_timer_support};
+use clap_sys::ext::voice_info::{CLAP_EXT_VOICE_INFO, clap_host_voice_info};
+use clap_sys::host::clap_host;
+use clap_sys::id::clap_id;
+use clap_sys::plugin::{clap_plugin, clap_plugin_descriptor};
+use clap_sys::version::CLAP_VERSION;
+
+use crate::ext::latency::PluginLatencyPrototype;
+use crate::ext::note_ports::{NoteDialect, NoteDialects, NotePortInfo, PluginNotePortsPrototype};
+use crate::ext::posix_fd_support::{PluginPosixFdSupportPrototype, PosixFdFlags};
+use crate::ext::render::{PluginRenderPrototype, RenderMode};
+use crate::ext::tail::{PluginTailPrototype, TailLength};
+use crate::ext::timer_support::{PluginTimerSupportPrototype, TimerRegistry};
+use crate::ext::voice_info::{PluginVoiceInfoPrototype, VoiceInfo, VoiceInfoFlags};
+use crate::ext::{ExtensionPrototype, ExtensionRegistry, PluginExtensionBase, extends};
+use crate::host::Host;
+use crate::plugin::{self, HasExtension, PluginName, PluginPrototype};
+use crate::process::ProcessContext;
+use crate::thread::{AudioThread, MainThread};
+use crate::{AbstractPrototype, ProcessResult};
+
+/// A host recording the calls of the plugins it created
+pub struct StubHost {
+    raw: clap_host,
+    next_timer_id: AtomicU32,
+    /// The ids of the extensions queried, supported or not
+    pub queried_extensions: Mutex<Vec<CString>>,
+    pub registered_timers: Mutex<Vec<clap_id>>,
+    pub unregistered_timers: Mutex<Vec<clap_id>>,
+    pub voice_info_changes: AtomicU32,
+    pub latency_changes: AtomicU32,
+    pub restart_requests: AtomicU32,
+    pub registered_fds: Mutex<Vec<(i32, clap_posix_fd_flags)>>,
+    pub modified_fds: Mutex<Vec<(i32, clap_posix_fd_flags)>>,
+    pub unregistered_fds: Mutex<Vec<i32>>,
+}
+impl StubHost {
+    /// Leaked, as the host has to outlive its plugins
+    pub fn leak() -> &'static StubHost {
+        let stub = Box::leak(Box::new(StubHost {
+            raw: clap_host {
+                clap_version: CLAP_VERSION,
+                host_data: ::core::ptr::null_mut(),
+                name: c"stub".as_ptr(),
+                vendor: ::core::ptr::null(),
+                url: ::core::ptr::null(),
+                version: c"0".as_ptr(),
+                get_extension: Some(get_extension),
+                request_restart: Some(request_restart),
+                request_process: Some(request),
+                request_callback: Some(request),
+            },
+            next_timer_id: AtomicU32::new(1),
+            queried_extensions: Mutex::new(Vec::new()),
+            registered_timers: Mutex::new(Vec::new()),
+            unregistered_timers: Mutex::new(Vec::new()),
+            voice_info_changes: AtomicU32::new(0),
+            latency_changes: AtomicU32::new(0),
+            restart_requests: AtomicU32::new(0),
+            registered_fds: Mutex::new(Vec::new()),
+            modified_fds: Mutex::new(Vec::new()),
+            unregistered_fds: Mutex::new(Vec::new()),
+        }));
+        stub.raw.host_data = stub as *mut StubHost as *mut c_void;
+        stub
+    }
+    pub fn host(&'static self) -> Host<'static> {
+        unsafe { Host::from_raw(&self.raw) }
+    }
+    fn of<'a>(host: *const clap_host) -> &'a StubHost {
+        unsafe { &*((*host).host_data as *const StubHost) }
+    }
+}
+unsafe extern "C" fn get_extension(host: *const clap_host, id: *const c_char) -> *const c_void {
+    let id = unsafe { CStr::from_ptr(id) };
+    StubHost::of(host)
+        .queried_extensions
+        .lock()
+        .unwrap()
+        .push(id.to_owned());
+    if id == CLAP_EXT_TIMER_SUPPORT {
+        &HOST_TIMER_SUPPORT as *const _ as *const c_void
+    } else if id == CLAP_EXT_THREAD_CHECK {
+        &HOST_THREAD_CHECK as *const _ as *const c_void
+    } else if id == CLAP_EXT_VOICE_INFO {
+        &HOST_VOICE_INFO as *const _ as *const c_void
+    } else if id == CLAP_EXT_LATENCY {
+        &HOST_LATENCY as *const _ as *const c_void
+    } else if id == CLAP_EXT_POSIX_FD_SUPPORT {
+        &HOST_POSIX_FD_SUPPORT as *const _ as *const c_void
+    } else {
+        ::core::ptr::null()
+    }
+}
+unsafe extern "C" fn request(_host: *const clap_host) {}
+unsafe extern "C" fn request_restart(host: *const clap_host) {
+    StubHost::of(host)
+        .restart_requests
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+/// Only knows of the audio threads, every thread being one
+static HOST_THREAD_CHECK: clap_host_thread_check = clap_host_thread_check {
+    is_main_thread: None,
+    is_audio_thread: Some(is_audio_thread),
+};
+unsafe extern "C" fn is_audio_thread(_host: *const clap_host) -> bool {
+    true
+}
+
+static HOST_TIMER_SUPPORT: clap_host_timer_support = clap_host_timer_support {
+    register_timer: Some(register_timer),
+    unregister_timer: Some(unregister_timer),
+};
+unsafe extern "C" fn register_timer(
+    host: *const clap_host,
+    _period_ms: u32,
+    timer_id: *mut clap_id,
+) -> bool {
+    let stub = StubHost::of(host);
+    let id = stub.next_timer_id.fetch_add(1, Ordering::Relaxed);
+    stub.registered_timers.lock().unwrap().push(id);
+    unsafe { *timer_id = id };
+    true
+}
+unsafe extern "C" fn unregister_timer(host: *const clap_host, timer_id: clap_id) -> bool {
+    StubHost::of(host)
+        .unregistered_timers
+        .lock()
+        .unwrap()
+        .push(timer_id);
+    true
+}
+static HOST_POSIX_FD_SUPPORT: clap_host_posix_fd_support = clap_host_posix_fd_support {
+    register_fd: Some(register_fd),
+    modify_fd: Some(modify_fd),
+    unregister_fd: Some(unregister_fd),
+};
+unsafe extern "C" fn register_fd(
+    host: *const clap_host,
+    fd: i32,
+    flags: clap_posix_fd_flags,
+) -> bool {
+    StubHost::of(host)
+        .registered_fds
+        .lock()
+        .unwrap()
+        .push((fd, flags));
+    true
+}
+unsafe extern "C" fn modify_fd(
+    host: *const clap_host,
+    fd: i32,
+    flags: clap_posix_fd_flags,
+) -> bool {
+    StubHost::of(host)
+        .modified_fds
+        .lock()
+        .unwrap()
+        .push((fd, flags));
+    true
+}
+unsafe extern "C" fn unregister_fd(host: *const clap_host, fd: i32) -> bool {
+    StubHost::of(host).unregistered_fds.lock().unwrap().push(fd);
+    true
+}
+static HOST_VOICE_INFO: clap_host_voice_info = clap_host_voice_info {
+    changed: Some(voice_info_changed),
+};
+unsafe extern "C" fn voice_info_changed(host: *const clap_host) {
+    StubHost::of(host)
+        .voice_info_changes
+        .fetch_add(1, Ordering::Relaxed);
+}
+static HOST_LATENCY: clap_host_latency = clap_host_latency {
+    changed: Some(latency_changed),
+};
+unsafe extern "C" fn latency_changed(host: *const clap_host) {
+    StubHost::of(host)
+        .latency_changes
+        .fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(ExtensionRegistry)]
+#[extensions(clap_plugin_draft)]
+#[repr(C)]
+pub struct TestPlugin<'host> {
+    base: clap_plugin,
+    pub timers: TestTimers<'host>,
+    pub draft: TestDraft<'host>,
+    pub voices: TestVoices<'host>,
+    pub tail: TestTail<'host>,
+    pub render: TestRender<'host>,
+    pub latency: TestLatency<'host>,
+    pub fds: TestFds<'host>,
+    pub note_ports: TestNotePorts<'host>,
+    /// What [`PluginPrototype::process`] returns
+    pub process_result: Mutex<ProcessResult>,
+}
+impl<'host> TestPlugin<'host> {
+    /// Creates the plugin for `host`, as a factory would
+    pub fn create(host: &'static StubHost) -> *const clap_plugin {
+        let descriptor: &'static mut clap_plugin_descriptor =
+            Box::leak(Box::new(unsafe { ::core::mem::zeroed() }));
+        descriptor.id = c"com.example.test".as_ptr();
+        let plugin = TestPlugin {
+            base: plugin::vtable::<TestPlugin>(descriptor),
+            timers: TestTimers {
+                base: TestTimersExtension::new(),
+                registry: TimerRegistry::new(),
+                ticks: AtomicU32::new(0),
+            },
+            draft: TestDraft {
+                base: clap_plugin_draft { reserved: 0 },
+                _host: ::core::marker::PhantomData,
+            },
+            voices: TestVoices {
+                base: TestVoicesExtension::new(),
+                info: Mutex::new(TestVoices::INFO),
+            },
+            tail: TestTail {
+                base: TestTailExtension::new(),
+                length: Mutex::new(TailLength::Infinite),
+            },
+            render: TestRender {
+                base: TestRenderExtension::new(),
+                mode: Mutex::new(RenderMode::Realtime),
+                hard_realtime: AtomicBool::new(false),
+            },
+            latency: TestLatency {
+                base: TestLatencyExtension::new(),
+                samples: AtomicU32::new(0),
+            },
+            fds: TestFds {
+                base: TestFdsExtension::new(),
+                ready: Mutex::new(Vec::new()),
+            },
+            note_ports: TestNotePorts {
+                base: TestNotePortsExtension::new(),
+                preferred_dialect: Mutex::new(NoteDialect::Clap),
+            },
+            process_result: Mutex::new(ProcessResult::Continue),
+        };
+        crate::factory::instantiate(plugin, host.host())
+    }
+    /// The plugin behind a `clap_plugin` returned by [`TestPlugin::create`]
+    pub fn of<'a>(plugin: *const clap_plugin) -> &'a TestPlugin<'host> {
+        unsafe { &*((*plugin).plugin_data as *const TestPlugin<'host>) }
+    }
+}
+impl<'host> AbstractPrototype<'host> for TestPlugin<'host> {
+    type Base = clap_plugin;
+    fn as_base(&self) -> &clap_plugin {
+        &self.base
+    }
+}
+impl<'host> PluginPrototype<'host> for TestPlugin<'host> {
+    fn init(&self, _main_thread: MainThread) -> bool {
+        true
+    }
+    fn activate(&self, _main_thread: MainThread, _: f64, _: u32, _: u32) -> bool {
+        true
+    }
+    fn deactivate(&self, _main_thread: MainThread) {}
+    fn start_processing(&self, _audio_thread: AudioThread) -> bool {
+        true
+    }
+    fn stop_processing(&self, _audio_thread: AudioThread) {}
+    fn reset(&self, _audio_thread: AudioThread) {}
+    fn process(
+        &self,
+        _audio_thread: AudioThread,
+        _process_context: &mut ProcessContext<'_>,
+    ) -> ProcessResult {
+        *self.process_result.lock().unwrap()
+    }
+    const POISON_ON_PANIC: bool = true;
+}
+
+#[extends(PluginTimerSupport)]
+pub struct TestTimers {
+    pub registry: TimerRegistry,
+    /// Ticks of the timers outside the registry
+    pub ticks: AtomicU32,
+}
+impl<'host> ExtensionPrototype<'host> for TestTimers<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginTimerSupportPrototype<'host> for TestTimers<'host> {
+    fn on_timer(&self, _main_thread: MainThread, _timer_id: clap_id) {
+        self.ticks.fetch_add(1, Ordering::Relaxed);
+    }
+    fn timers(&self) -> Option<&TimerRegistry> {
+        Some(&self.registry)
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::timer_support::clap_plugin_timer_support>
+    for TestPlugin<'host>
+{
+    type ExtensionType = TestTimers<'host>;
+    fn get_extension(&self) -> &TestTimers<'host> {
+        &self.timers
+    }
+}
+
+/// An extension unknown to the crate, which went by a draft id before
+#[allow(non_camel_case_types)]
+#[repr(C)]
+pub struct clap_plugin_draft {
+    pub reserved: u32,
+}
+impl PluginExtensionBase for clap_plugin_draft {
+    const ID: &'static CStr = c"com.example.draft/1";
+    const COMPAT_IDS: &'static [&'static CStr] = &[c"com.example.draft.draft/0"];
+}
+pub struct TestDraft<'host> {
+    pub base: clap_plugin_draft,
+    _host: ::core::marker::PhantomData<&'host ()>,
+}
+impl<'host> AbstractPrototype<'host> for TestDraft<'host> {
+    type Base = clap_plugin_draft;
+    fn as_base(&self) -> &clap_plugin_draft {
+        &self.base
+    }
+}
+impl<'host> ExtensionPrototype<'host> for TestDraft<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> HasExtension<'host, clap_plugin_draft> for TestPlugin<'host> {
+    type ExtensionType = TestDraft<'host>;
+    fn get_extension(&self) -> &TestDraft<'host> {
+        &self.draft
+    }
+}
+
+#[extends(PluginVoiceInfo)]
+pub struct TestVoices {
+    /// Starts out as [`TestVoices::INFO`]
+    pub info: Mutex<VoiceInfo>,
+}
+impl TestVoices<'_> {
+    pub const INFO: VoiceInfo = VoiceInfo {
+        voice_count: 8,
+        voice_capacity: 16,
+        flags: VoiceInfoFlags::SUPPORTS_OVERLAPPING_NOTES,
+    };
+}
+impl<'host> ExtensionPrototype<'host> for TestVoices<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginVoiceInfoPrototype<'host> for TestVoices<'host> {
+    fn get(&self, _main_thread: MainThread) -> Option<VoiceInfo> {
+        Some(*self.info.lock().unwrap())
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::voice_info::clap_plugin_voice_info>
+    for TestPlugin<'host>
+{
+    type ExtensionType = TestVoices<'host>;
+    fn get_extension(&self) -> &TestVoices<'host> {
+        &self.voices
+    }
+}
+
+#[extends(PluginTail)]
+pub struct TestTail {
+    pub length: Mutex<TailLength>,
+}
+impl<'host> ExtensionPrototype<'host> for TestTail<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginTailPrototype<'host> for TestTail<'host> {
+    fn get(&self) -> TailLength {
+        *self.length.lock().unwrap()
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::tail::clap_plugin_tail> for TestPlugin<'host> {
+    type ExtensionType = TestTail<'host>;
+    fn get_extension(&self) -> &TestTail<'host> {
+        &self.tail
+    }
+}
+
+#[extends(PluginRender)]
+pub struct TestRender {
+    pub mode: Mutex<RenderMode>,
+    /// Refuses to render offline while set
+    pub hard_realtime: AtomicBool,
+}
+impl<'host> ExtensionPrototype<'host> for TestRender<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginRenderPrototype<'host> for TestRender<'host> {
+    fn has_hard_realtime_requirement(&self, _main_thread: MainThread) -> bool {
+        self.hard_realtime.load(Ordering::Relaxed)
+    }
+    fn set(&self, main_thread: MainThread, mode: RenderMode) -> bool {
+        if mode == RenderMode::Offline && self.has_hard_realtime_requirement(main_thread) {
+            return false;
+        }
+        *self.mode.lock().unwrap() = mode;
+        true
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::render::clap_plugin_render> for TestPlugin<'host> {
+    type ExtensionType = TestRender<'host>;
+    fn get_extension(&self) -> &TestRender<'host> {
+        &self.render
+    }
+}
+
+#[extends(PluginLatency)]
+pub struct TestLatency {
+    pub samples: AtomicU32,
+}
+impl<'host> ExtensionPrototype<'host> for TestLatency<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginLatencyPrototype<'host> for TestLatency<'host> {
+    fn get(&self, _main_thread: MainThread) -> u32 {
+        self.samples.load(Ordering::Relaxed)
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::latency::clap_plugin_latency> for TestPlugin<'host> {
+    type ExtensionType = TestLatency<'host>;
+    fn get_extension(&self) -> &TestLatency<'host> {
+        &self.latency
+    }
+}
+
+#[extends(PluginPosixFdSupport)]
+pub struct TestFds {
+    /// The file descriptors reported ready, in order
+    pub ready: Mutex<Vec<(i32, PosixFdFlags)>>,
+}
+impl<'host> ExtensionPrototype<'host> for TestFds<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginPosixFdSupportPrototype<'host> for TestFds<'host> {
+    fn on_fd(&self, _main_thread: MainThread, fd: i32, flags: PosixFdFlags) {
+        self.ready.lock().unwrap().push((fd, flags));
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::posix_fd_support::clap_plugin_posix_fd_support>
+    for TestPlugin<'host>
+{
+    type ExtensionType = TestFds<'host>;
+    fn get_extension(&self) -> &TestFds<'host> {
+        &self.fds
+    }
+}
+
+#[extends(PluginNotePorts)]
+pub struct TestNotePorts {
+    /// Preferred by the only input port, which supports [`TestNotePorts::SUPPORTED_DIALECTS`]
+    pub preferred_dialect: Mutex<NoteDialect>,
+}
+impl TestNotePorts<'_> {
+    pub const ID: clap_id = 7;
+    pub const SUPPORTED_DIALECTS: NoteDialects = NoteDialects::CLAP.union(NoteDialects::MIDI);
+}
+impl<'host> ExtensionPrototype<'host> for TestNotePorts<'host> {
+    type Parent = TestPlugin<'host>;
+}
+impl<'host> PluginNotePortsPrototype<'host> for TestNotePorts<'host> {
+    fn count(&self, _main_thread: MainThread, is_input: bool) -> u32 {
+        is_input as u32
+    }
+    fn get(
+        &self,
+        _main_thread: MainThread,
+        index: u32,
+        is_input: bool,
+    ) -> Option<NotePortInfo<'_>> {
+        (index == 0 && is_input).then(|| NotePortInfo {
+            id: Self::ID,
+            supported_dialects: Self::SUPPORTED_DIALECTS,
+            preferred_dialect: *self.preferred_dialect.lock().unwrap(),
+            name: PluginName::from_c_str(c"notes"),
+        })
+    }
+}
+impl<'host> HasExtension<'host, clap_sys::ext::note_ports::clap_plugin_note_ports>
+    for TestPlugin<'host>
+{
+    type ExtensionType = TestNotePorts<'host>;
+    fn get_extension(&self) -> &TestNotePorts<'host> {
+        &self.note_ports
+    }
+}
@@ -0,0 +1,149 @@
+//! Thread
+//!
+//! Every CLAP callback is bound to a thread, as noted at the top of its
+//! documentation, like `main-thread` or `audio-thread`. [`MainThread`] and
+//! [`AudioThread`] are proof of running on one of them: the thunks hand them to
+//! the trait methods, and they can neither be sent nor shared across threads.
+//!
+//! Anything `main-thread` takes a [`MainThread`], so it cannot be reached from
+//! [`PluginPrototype::process`] without a hop, either through
+//! [`Host::request_callback`] and [`PluginPrototype::on_main_thread`], or by
+//! asking the host with [`MainThread::check`].
+//!
+//! In debug builds, the thunks verify the thread through `clap_host_thread_check`
+//! when the host provides it, and log a mismatch as host misbehaviour.
+
+use core::marker::PhantomData;
+
+use clap_sys::plugin::clap_plugin;
+
+use crate::host::Host;
+use crate::host::ext::thread_check::HostThreadCheck;
+#[cfg(debug_assertions)]
+use crate::log::Severity;
+use crate::plugin::PluginPrototype;
+#[cfg(debug_assertions)]
+use crate::plugin::instance::PluginInstance;
+
+/// Proof of running on the main thread of the host
+#[derive(Debug, Clone, Copy)]
+pub struct MainThread {
+    _not_send: PhantomData<*const ()>,
+}
+impl MainThread {
+    /// # Safety
+    ///
+    /// The caller must be on the main thread of the host.
+    #[inline]
+    pub const unsafe fn new_unchecked() -> MainThread {
+        MainThread {
+            _not_send: PhantomData,
+        }
+    }
+    /// `thread-safe`
+    ///
+    /// Asks the host whether this is its main thread, [`None`] if not or if it cannot tell.
+    pub fn check(host: Host<'_>) -> Option<MainThread> {
+        let thread_check = host.get_extension::<HostThreadCheck>()?;
+        thread_check
+            .is_main_thread()?
+            .then(|| unsafe { MainThread::new_unchecked() })
+    }
+}
+
+/// Proof of running on an audio thread of the host
+#[derive(Debug, Clone, Copy)]
+pub struct AudioThread {
+    _not_send: PhantomData<*const ()>,
+}
+impl AudioThread {
+    /// # Safety
+    ///
+    /// The caller must be on an audio thread of the host.
+    #[inline]
+    pub const unsafe fn new_unchecked() -> AudioThread {
+        AudioThread {
+            _not_send: PhantomData,
+        }
+    }
+    /// `thread-safe`
+    ///
+    /// Asks the host whether this is one of its audio threads, [`None`] if not or if it cannot tell.
+    pub fn check(host: Host<'_>) -> Option<AudioThread> {
+        let thread_check = host.get_extension::<HostThreadCheck>()?;
+        thread_check
+            .is_audio_thread()?
+            .then(|| unsafe { AudioThread::new_unchecked() })
+    }
+}
+
+/// The token for a `main-thread` callback of the plugin.
+#[inline]
+pub(crate) fn main_thread<'host, P>(
+    plugin: *const clap_plugin,
+    callback: &'static str,
+) -> MainThread
+where
+    P: PluginPrototype<'host>,
+{
+    #[cfg(debug_assertions)]
+    verify::<P>(plugin, callback, true);
+    #[cfg(not(debug_assertions))]
+    let _ = (plugin, callback);
+    // SAFETY: The host only calls `main-thread` callbacks on the main thread
+    unsafe { MainThread::new_unchecked() }
+}
+/// The token for an `audio-thread` callback of the plugin.
+#[inline]
+pub(crate) fn audio_thread<'host, P>(
+    plugin: *const clap_plugin,
+    callback: &'static str,
+) -> AudioThread
+where
+    P: PluginPrototype<'host>,
+{
+    #[cfg(debug_assertions)]
+    verify::<P>(plugin, callback, false);
+    #[cfg(not(debug_assertions))]
+    let _ = (plugin, callback);
+    // SAFETY: The host only calls `audio-thread` callbacks on an audio thread
+    unsafe { AudioThread::new_unchecked() }
+}
+#[cfg(debug_assertions)]
+fn verify<P>(plugin: *const clap_plugin, callback: &'static str, is_main: bool) {
+    let Some(instance) = PluginInstance::<P>::from_plugin(plugin) else {
+        return;
+    };
+    let Some(thread_check) = instance.host().get_extension::<HostThreadCheck>() else {
+        return;
+    };
+    // A host which cannot tell is taken at its word
+    if is_main && thread_check.is_main_thread() == Some(false) {
+        instance.logger().log(
+            Severity::HostMisbehaving,
+            format_args!("{callback} called off the main thread"),
+        );
+    }
+    if !is_main && thread_check.is_audio_thread() == Some(false) {
+        instance.logger().audio(
+            Severity::HostMisbehaving,
+            format_args!("{callback} called off the audio threads"),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::StubHost;
+
+    #[test]
+    fn unknown_threads() {
+        let host = StubHost::leak().host();
+        let thread_check = host.get_extension::<HostThreadCheck>().unwrap();
+        assert_eq!(thread_check.is_main_thread(), None);
+        assert!(MainThread::check(host).is_none());
+        assert_eq!(thread_check.is_audio_thread(), Some(true));
+        assert!(AudioThread::check(host).is_some());
+    }
+}
@@ -0,0 +1,7 @@
+//! Diagnostics of `plugin_descriptor!`, the expected ones are kept next to each case,
+//! regenerate them with `TRYBUILD=overwrite cargo test --test descriptor`.
+
+#[test]
+fn compile_errors() {
+    trybuild::TestCases::new().compile_fail("tests/ui/descriptor/*.rs");
+}
@@ -0,0 +1,10 @@
+use clap_prototype::plugin::plugin_descriptor;
+
+fn main() {
+    let _ = plugin_descriptor! {
+        id: "com.example.gain",
+        name: "Gain",
+        name: "Gain",
+        version: "1.0.0",
+    };
+}
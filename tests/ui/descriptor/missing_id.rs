@@ -0,0 +1,8 @@
+use clap_prototype::plugin::plugin_descriptor;
+
+fn main() {
+    let _ = plugin_descriptor! {
+        name: "Gain",
+        version: "1.0.0",
+    };
+}
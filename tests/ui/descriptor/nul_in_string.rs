@@ -0,0 +1,9 @@
+use clap_prototype::plugin::plugin_descriptor;
+
+fn main() {
+    let _ = plugin_descriptor! {
+        id: "com.example.gain",
+        name: "Ga\0in",
+        version: "1.0.0",
+    };
+}
@@ -0,0 +1,14 @@
+use clap_prototype::factory::PluginBundle;
+
+static BUNDLE: PluginBundle = PluginBundle::new(&[]);
+
+clap_prototype::entrypoint!(factories: {
+    "clap.preset-discovery-factory/2" => fn presets,
+    "clap.preset-discovery-factory/draft-2" => fn presets,
+});
+
+fn presets() -> Option<&'static PluginBundle<'static>> {
+    Some(&BUNDLE)
+}
+
+fn main() {}
@@ -0,0 +1,7 @@
+fn init(_plugin_path: &core::ffi::CStr) -> bool {
+    true
+}
+
+clap_prototype::entrypoint!(init: init);
+
+fn main() {}
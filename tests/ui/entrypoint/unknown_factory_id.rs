@@ -0,0 +1,7 @@
+use clap_prototype::factory::PluginBundle;
+
+static BUNDLE: PluginBundle = PluginBundle::new(&[]);
+
+clap_prototype::entrypoint!(factories: { "clap.preset-discovery-factory" => BUNDLE });
+
+fn main() {}